@@ -0,0 +1,80 @@
+use std::hint::black_box;
+
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::BatchSize;
+use criterion::Criterion;
+use criterion::Throughput;
+
+use rox::parser::Parser;
+use rox::scanner::Scanner;
+
+const SNIPPET: &str = r#"
+// Counts down and builds up a greeting along the way
+class Greeter {
+  init(name) {
+    this.name = name;
+  }
+
+  greet(times) {
+    var greeting = "héllo, wörld ✓ ";
+    for (var i = 0; i < times; i = i + 1) {
+      greeting = greeting + this.name;
+    }
+    return greeting;
+  }
+}
+
+fun fib(n) {
+  if (n <= 1) return n;
+  return fib(n - 2) + fib(n - 1);
+}
+
+var greeter = Greeter("rox");
+print greeter.greet(3) + " " + fib(10.5);
+"#;
+
+/// Repeats the snippet until the source is at least `size` bytes long
+fn source(size: usize) -> String {
+    SNIPPET.repeat(size / SNIPPET.len() + 1)
+}
+
+fn scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan");
+    group.sample_size(10);
+
+    for megabytes in [1, 4] {
+        let source = source(megabytes * 1024 * 1024);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(format!("{}MB", megabytes), |b| {
+            b.iter_batched(
+                || source.clone(),
+                |source| Scanner::new(black_box(source)).scan_tokens(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+
+    let source = source(4 * 1024 * 1024);
+    let tokens = Scanner::new(source.clone()).scan_tokens().unwrap();
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.bench_function("4MB", |b| {
+        b.iter_batched(
+            || tokens.clone(),
+            |tokens| Parser::new(black_box(tokens)).parse(),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, scan, parse);
+criterion_main!(benches);
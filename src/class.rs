@@ -0,0 +1,93 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::function::Function;
+use crate::scanner::Token;
+use crate::value::Value;
+
+#[derive(Debug)]
+pub struct Class {
+    pub name: String,
+    superclass: Option<Rc<Class>>,
+    methods: HashMap<String, Rc<Function>>,
+}
+
+impl Class {
+    pub fn new(
+        name: String,
+        superclass: Option<Rc<Class>>,
+        methods: HashMap<String, Rc<Function>>,
+    ) -> Class {
+        Class {
+            name,
+            superclass,
+            methods,
+        }
+    }
+
+    /// Methods not defined on this class are looked up on the superclass chain
+    pub fn find_method(&self, name: &str) -> Option<Rc<Function>> {
+        match (self.methods.get(name), &self.superclass) {
+            (Some(method), _) => Some(method.clone()),
+            (None, Some(superclass)) => superclass.find_method(name),
+            (None, None) => None,
+        }
+    }
+
+    /// Calling a class takes the same arguments as its initializer
+    pub fn arity(&self) -> usize {
+        self.find_method("init")
+            .map_or(0, |initializer| initializer.arity())
+    }
+}
+
+impl PartialEq for Class {
+    fn eq(&self, other: &Class) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+pub struct Instance {
+    pub class: Rc<Class>,
+    fields: HashMap<String, Value>,
+}
+
+impl Instance {
+    pub fn new(class: Rc<Class>) -> Instance {
+        Instance {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Fields shadow methods. Methods are bound to the instance so that 'this' works when they
+    /// are called later
+    pub fn get(instance: &Rc<RefCell<Instance>>, name: &Token) -> Option<Value> {
+        if let Some(value) = instance.borrow().fields.get(&name.lexeme) {
+            return Some(value.clone());
+        }
+
+        let method = instance.borrow().class.find_method(&name.lexeme);
+        method.map(|method| Value::Function(Rc::new(method.bind(instance.clone()))))
+    }
+
+    pub fn set(&mut self, name: &Token, value: Value) {
+        self.fields.insert(name.lexeme.clone(), value);
+    }
+}
+
+impl PartialEq for Instance {
+    fn eq(&self, other: &Instance) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// Fields can refer back to the instance so we avoid printing them
+impl std::fmt::Debug for Instance {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Instance")
+            .field("class", &self.class.name)
+            .finish()
+    }
+}
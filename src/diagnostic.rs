@@ -0,0 +1,136 @@
+use crate::error::ErrorKind;
+use crate::error::NoteKind;
+use crate::error::RoxError;
+use crate::scanner::Span;
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Formats errors for people to read, showing the offending line of source with the error's span
+/// underlined along with any notes and help attached to it
+pub struct Renderer {
+    color: bool,
+}
+
+impl Renderer {
+    pub fn new(color: bool) -> Renderer {
+        Renderer { color }
+    }
+
+    /// Renders an error found in `source`, which was read from `path`
+    pub fn render(&self, error: &RoxError, source: &str, path: &str) -> String {
+        let mut output = format!(
+            "{}: {}\n",
+            self.paint(RED, Renderer::title(error.kind)),
+            self.paint(BOLD, &error.message)
+        );
+
+        if error.kind != ErrorKind::Io {
+            output += &self.snippet(source, path, error.span, '^', RED);
+        }
+
+        for note in &error.notes {
+            let label = match note.kind {
+                NoteKind::Note => "note",
+                NoteKind::Help => "help",
+            };
+            match note.span {
+                Some(span) => {
+                    output += &format!("{}: {}\n", self.paint(CYAN, label), note.message);
+                    output += &self.snippet(source, path, span, '-', CYAN);
+                }
+                None => output += &format!(" = {}: {}\n", self.paint(BOLD, label), note.message),
+            }
+        }
+
+        if !error.trace.is_empty() {
+            output += &format!("{}\n", self.paint(BOLD, "stack trace:"));
+            for frame in error.trace.iter() {
+                output += &format!("    at {} ({}:{})\n", frame.function, path, frame.line);
+            }
+        }
+
+        output
+    }
+
+    fn title(kind: ErrorKind) -> &'static str {
+        match kind {
+            ErrorKind::Scan | ErrorKind::Parse => "syntax error",
+            ErrorKind::Resolve => "error",
+            ErrorKind::Runtime => "runtime error",
+            ErrorKind::Io => "error",
+        }
+    }
+
+    /// The location of the span followed by its line of source, underlined with `marker`. Spans
+    /// that run over several lines are only underlined up to the end of the first one
+    fn snippet(&self, source: &str, path: &str, span: Span, marker: char, color: &str) -> String {
+        let gutter = " ".repeat(span.line.to_string().len());
+        let location = format!(
+            "{}{} {}:{}:{}\n",
+            gutter,
+            self.paint(BLUE, "-->"),
+            path,
+            span.line,
+            span.column
+        );
+
+        let (line, line_end) = match Renderer::line_at(source, span.offset) {
+            Some(line) => line,
+            None => return location,
+        };
+
+        // Keep any tabs before the span so that the markers line up with the source
+        let padding: String = line
+            .chars()
+            .take(span.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        let end = std::cmp::min(span.offset + span.length, line_end);
+        let width = source
+            .get(span.offset..end)
+            .map_or(0, |text| text.chars().count());
+        let markers: String = std::iter::repeat_n(marker, std::cmp::max(width, 1)).collect();
+
+        let bar = self.paint(BLUE, "|");
+        format!(
+            "{}{} {}\n{} {} {}\n{} {} {}{}\n",
+            location,
+            gutter,
+            bar,
+            self.paint(BLUE, &span.line.to_string()),
+            bar,
+            line,
+            gutter,
+            bar,
+            padding,
+            self.paint(color, &markers)
+        )
+    }
+
+    /// The whole line of source containing the byte `offset` along with the offset the line ends
+    /// at, or `None` when the offset doesn't fall within the source
+    fn line_at(source: &str, offset: usize) -> Option<(&str, usize)> {
+        if offset > source.len() || !source.is_char_boundary(offset) {
+            return None;
+        }
+
+        let start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |i| offset + i);
+        Some((source[start..end].trim_end_matches('\r'), end))
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
@@ -1,9 +1,15 @@
 use crate::scanner::Token;
 use crate::value::Value;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 
+/// A single scope. Scopes are reference counted and linked to their enclosing scope so that
+/// functions can keep the scope they were declared in alive after it has been exited
+#[derive(Default)]
 pub struct Environment {
     values: HashMap<String, Option<Value>>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
 pub enum Error {
@@ -14,9 +20,28 @@ impl Environment {
     pub fn new() -> Environment {
         Environment {
             values: HashMap::new(),
+            enclosing: None,
         }
     }
 
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Environment {
+        Environment {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    /// Removes every variable, handing them back so that they can be dropped once this scope is
+    /// no longer borrowed
+    pub fn clear(&mut self) -> HashMap<String, Option<Value>> {
+        std::mem::take(&mut self.values)
+    }
+
+    /// The names and values defined directly in this scope, in no particular order
+    pub fn values(&self) -> impl Iterator<Item = (&String, &Option<Value>)> {
+        self.values.iter()
+    }
+
     pub fn define(&mut self, name: String, value: Option<Value>) {
         self.values.insert(name, value);
     }
@@ -25,17 +50,47 @@ impl Environment {
         if self.values.contains_key(&name.lexeme) {
             self.values.insert(name.lexeme.clone(), Some(value.clone()));
             true
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign(name, value)
         } else {
             false
         }
     }
 
+    /// Assigns to the variable in the scope the given number of hops out from this one, as
+    /// worked out by the resolver
+    pub fn assign_at(&mut self, distance: usize, name: &str, value: &Value) -> bool {
+        if distance == 0 {
+            self.values.insert(name.to_string(), Some(value.clone()));
+            true
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign_at(distance - 1, name, value)
+        } else {
+            false
+        }
+    }
+
+    /// Looks up the variable in the scope the given number of hops out from this one, as worked
+    /// out by the resolver
+    pub fn get_at(&self, distance: usize, name: &str) -> Result<Option<Value>, Error> {
+        match (distance, &self.enclosing) {
+            (0, _) => self
+                .values
+                .get(name)
+                .cloned()
+                .ok_or(Error::NameDoesNotExist),
+            (_, Some(enclosing)) => enclosing.borrow().get_at(distance - 1, name),
+            (_, None) => Err(Error::NameDoesNotExist),
+        }
+    }
+
     pub fn get(&self, name: &Token) -> Result<Option<Value>, Error> {
         let option = self.values.get(&name.lexeme);
 
-        match option {
-            Some(inner) => Ok(inner.clone()),
-            None => Err(Error::NameDoesNotExist),
+        match (option, &self.enclosing) {
+            (Some(inner), _) => Ok(inner.clone()),
+            (None, Some(enclosing)) => enclosing.borrow().get(name),
+            (None, None) => Err(Error::NameDoesNotExist),
         }
     }
 }
@@ -1,5 +1,111 @@
-pub fn report(line: usize, where_: &str, message: &str) {
-    println!("[line {}] Error {}: {}", line, where_, message);
+use crate::scanner::Span;
+use crate::scanner::Token;
+use crate::scanner::TokenType;
 
-    // hadError = true;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    Scan,
+    Parse,
+    Resolve,
+    Runtime,
+    Io,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoteKind {
+    Note,
+    /// A suggestion for how to fix the error
+    Help,
+}
+
+/// Extra context attached to an error, optionally pointing at another part of the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Note {
+    pub kind: NoteKind,
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+/// A function call that was still running when a runtime error happened, along with the line it
+/// had reached
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function: String,
+    pub line: usize,
+}
+
+/// An error from any stage of running a script. Nothing is printed when these are created so
+/// that the caller can decide how to present them
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoxError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub span: Span,
+    /// The text of the token the error was found at, or `None` when it was found at the end of
+    /// the input or isn't tied to a token
+    pub lexeme: Option<String>,
+    pub notes: Vec<Note>,
+    /// The active calls for runtime errors, innermost first
+    pub trace: Vec<Frame>,
+}
+
+impl RoxError {
+    pub fn new(kind: ErrorKind, message: String, span: Span, lexeme: Option<String>) -> RoxError {
+        RoxError {
+            kind,
+            message,
+            span,
+            lexeme,
+            notes: Vec::new(),
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn at_token(kind: ErrorKind, token: &Token, message: &str) -> RoxError {
+        let lexeme = if token.type_ == TokenType::Eof {
+            None
+        } else {
+            Some(token.lexeme.clone())
+        };
+        RoxError::new(kind, message.to_string(), token.span, lexeme)
+    }
+
+    pub fn with_note(mut self, message: &str, span: Option<Span>) -> RoxError {
+        self.notes.push(Note {
+            kind: NoteKind::Note,
+            message: message.to_string(),
+            span,
+        });
+        self
+    }
+
+    pub fn with_help(mut self, message: &str) -> RoxError {
+        self.notes.push(Note {
+            kind: NoteKind::Help,
+            message: message.to_string(),
+            span: None,
+        });
+        self
+    }
+}
+
+impl std::fmt::Display for RoxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.kind == ErrorKind::Io {
+            return write!(f, "Error: {}", self.message);
+        }
+
+        let where_ = match (&self.lexeme, self.kind) {
+            (Some(lexeme), _) => format!(" at '{}'", lexeme),
+            (None, ErrorKind::Parse) => " at end".to_string(),
+            (None, _) => String::new(),
+        };
+        write!(
+            f,
+            "[line {}, column {}] Error{}: {}",
+            self.span.line, self.span.column, where_, self.message
+        )
+    }
+}
+
+impl std::error::Error for RoxError {}
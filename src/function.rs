@@ -0,0 +1,175 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::class::Instance;
+use crate::environment::Environment;
+use crate::parser::FunctionDeclaration;
+use crate::value::Value;
+
+pub struct Function {
+    pub declaration: Rc<FunctionDeclaration>,
+    pub closure: Rc<RefCell<Environment>>,
+    pub is_initializer: bool,
+}
+
+impl Function {
+    pub fn new(
+        declaration: Rc<FunctionDeclaration>,
+        closure: Rc<RefCell<Environment>>,
+        is_initializer: bool,
+    ) -> Function {
+        Function {
+            declaration,
+            closure,
+            is_initializer,
+        }
+    }
+
+    /// Creates a copy of the method with a new scope, holding 'this', wrapped around its closure
+    pub fn bind(&self, instance: Rc<RefCell<Instance>>) -> Function {
+        let mut environment = Environment::with_enclosing(self.closure.clone());
+        environment.define("this".to_string(), Some(Value::Instance(instance)));
+        Function::new(
+            self.declaration.clone(),
+            Rc::new(RefCell::new(environment)),
+            self.is_initializer,
+        )
+    }
+
+    pub fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.declaration.name.lexeme
+    }
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Function) -> bool {
+        Rc::ptr_eq(&self.declaration, &other.declaration)
+            && Rc::ptr_eq(&self.closure, &other.closure)
+    }
+}
+
+/// The closure usually contains the function itself so we avoid printing it
+impl std::fmt::Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Function")
+            .field("name", &self.name())
+            .finish()
+    }
+}
+
+/// A Rust function callable from scripts, which can capture state from the host. Returning an
+/// error raises a runtime error at the call. Natives return a `NativeError` rather than an
+/// `EvaluationError` because they have no token of their own to report it at
+pub type NativeFn = Rc<dyn Fn(&[Value]) -> Result<Value, NativeError>>;
+
+/// Why a native function failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum NativeError {
+    /// An argument has the wrong type
+    ArgumentType(String),
+    /// An argument has the right type but a value the function can't work with, like an index
+    /// that is out of range
+    ArgumentValue(String),
+    /// Anything else that goes wrong on the Rust side
+    Failed(String),
+}
+
+impl NativeError {
+    pub fn message(&self) -> &str {
+        match self {
+            NativeError::ArgumentType(message)
+            | NativeError::ArgumentValue(message)
+            | NativeError::Failed(message) => message,
+        }
+    }
+}
+
+impl std::fmt::Display for NativeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+/// A function implemented in Rust and exposed to scripts through the global scope
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: usize,
+    pub function: NativeFn,
+}
+
+impl NativeFunction {
+    pub fn new(name: &str, arity: usize, function: NativeFn) -> NativeFunction {
+        NativeFunction {
+            name: name.to_string(),
+            arity,
+            function,
+        }
+    }
+
+    pub fn call(&self, arguments: &[Value]) -> Result<Value, NativeError> {
+        (self.function)(arguments)
+    }
+}
+
+impl std::fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish()
+    }
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &NativeFunction) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// Seconds since the Unix epoch, for timing scripts
+pub fn clock(_arguments: &[Value]) -> Result<Value, NativeError> {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or(0.0);
+    Ok(Value::Double(seconds))
+}
+
+/// The number of Unicode scalar values in a string
+pub fn len(arguments: &[Value]) -> Result<Value, NativeError> {
+    match &arguments[0] {
+        Value::String(string) => Ok(Value::Int(string.chars().count() as i64)),
+        _ => Err(NativeError::ArgumentType(
+            "Can only take the length of a string".to_string(),
+        )),
+    }
+}
+
+/// The Unicode scalar value at a zero-based index into a string, as a string of its own
+pub fn char_at(arguments: &[Value]) -> Result<Value, NativeError> {
+    let (string, index) = match (&arguments[0], &arguments[1]) {
+        (Value::String(string), Value::Int(index)) => (string, *index as f64),
+        (Value::String(string), Value::Double(index)) => (string, *index),
+        _ => {
+            return Err(NativeError::ArgumentType(
+                "Arguments must be a string and a number".to_string(),
+            ))
+        }
+    };
+
+    if index < 0.0 || index.fract() != 0.0 {
+        return Err(NativeError::ArgumentValue(
+            "String index must be a whole number that isn't negative".to_string(),
+        ));
+    }
+
+    string
+        .chars()
+        .nth(index as usize)
+        .map(|c| Value::String(c.to_string()))
+        .ok_or_else(|| NativeError::ArgumentValue(format!("String index {} out of range", index)))
+}
@@ -1,5 +1,21 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::class::Class;
+use crate::class::Instance;
 use crate::environment::Environment;
+use crate::error::ErrorKind;
+use crate::error::Frame;
+use crate::error::RoxError;
+use crate::function;
+use crate::function::Function;
+use crate::function::NativeError;
+use crate::function::NativeFn;
+use crate::function::NativeFunction;
 use crate::parser::Expr;
+use crate::parser::FunctionDeclaration;
 use crate::parser::Stmt;
 use crate::scanner::Token;
 use crate::scanner::TokenType;
@@ -9,101 +25,454 @@ use crate::value::Value;
 pub enum EvaluationError {
     InvalidUnaryOperand(Token, String),
     InvalidBinaryOperand(Token, String),
-    VariableDoesNotExist,
-    InvalidAssignment,
+    VariableDoesNotExist(Token, String),
+    InvalidAssignment(Token, String),
+    NotCallable(Token, String),
+    ArityMismatch(Token, String),
+    NotAnInstance(Token, String),
+    UndefinedProperty(Token, String),
+    InvalidSuperclass(Token, String),
+    NativeError(Token, NativeError),
+    IntegerOverflow(Token, String),
+    DivisionByZero(Token, String),
+    StackOverflow(Token, String),
+}
+
+impl EvaluationError {
+    fn parts(&self) -> (&Token, &str) {
+        match self {
+            EvaluationError::InvalidUnaryOperand(token, message)
+            | EvaluationError::InvalidBinaryOperand(token, message)
+            | EvaluationError::VariableDoesNotExist(token, message)
+            | EvaluationError::InvalidAssignment(token, message)
+            | EvaluationError::NotCallable(token, message)
+            | EvaluationError::ArityMismatch(token, message)
+            | EvaluationError::NotAnInstance(token, message)
+            | EvaluationError::UndefinedProperty(token, message)
+            | EvaluationError::InvalidSuperclass(token, message)
+            | EvaluationError::IntegerOverflow(token, message)
+            | EvaluationError::DivisionByZero(token, message)
+            | EvaluationError::StackOverflow(token, message) => (token, message),
+            EvaluationError::NativeError(token, error) => (token, error.message()),
+        }
+    }
+}
+
+impl From<EvaluationError> for RoxError {
+    fn from(err: EvaluationError) -> RoxError {
+        let (token, message) = err.parts();
+        RoxError::at_token(ErrorKind::Runtime, token, message)
+    }
+}
+
+/// How control leaves a statement. A 'return' unwinds through any enclosing blocks and loops
+/// until it reaches the function call that is executing them
+enum Completion {
+    Normal,
+    Return(Value),
 }
 
-pub struct Interpreter {}
+/// How deeply calls can nest before raising an error. Each call takes a lot of the Rust stack, so
+/// this has to be low enough to stop runaway recursion before it overflows the thread's stack and
+/// aborts the host process, even in debug builds. The same limit as clox
+const MAX_FRAMES: usize = 64;
+
+/// A call to a Lox function that hasn't returned yet
+struct CallFrame {
+    function: String,
+    /// The line the function was called from
+    line: usize,
+}
+
+pub struct Interpreter {
+    globals: Rc<RefCell<Environment>>,
+    environment: Rc<RefCell<Environment>>,
+    frames: Vec<CallFrame>,
+    /// Captured by the innermost call when a runtime error starts unwinding, before the frames
+    /// are popped
+    trace: Option<Vec<Frame>>,
+}
+
+/// A function keeps the scope it was declared in alive, and that scope holds the function, so the
+/// two are never freed by reference counting alone. Emptying the global scope breaks the cycles
+/// for everything declared at the top level. Closures over a local scope that still holds them,
+/// such as a counter returned from a function, are leaked as there is no cycle collector
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        let globals = self.globals.borrow_mut().clear();
+        drop(globals);
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
 
 impl Interpreter {
     pub fn new() -> Interpreter {
-        Interpreter {}
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        let mut interpreter = Interpreter {
+            environment: globals.clone(),
+            globals,
+            frames: Vec::new(),
+            trace: None,
+        };
+
+        interpreter.define_native("clock", 0, Rc::new(function::clock));
+        interpreter.define_native("len", 1, Rc::new(function::len));
+        interpreter.define_native("charAt", 2, Rc::new(function::char_at));
+
+        interpreter
     }
 
-    pub fn interpret(&mut self, statements: &Vec<Stmt>) {
-        let mut environments = vec![Environment::new()];
+    pub fn define_native(&mut self, name: &str, arity: usize, function: NativeFn) {
+        let native = NativeFunction::new(name, arity, function);
+        self.globals.borrow_mut().define(
+            name.to_string(),
+            Some(Value::NativeFunction(Rc::new(native))),
+        );
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.globals
+            .borrow_mut()
+            .define(name.to_string(), Some(value));
+    }
+
+    /// Globals that have been declared without a value read as nil
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.globals
+            .borrow()
+            .get_at(0, name)
+            .ok()
+            .map(|value| value.unwrap_or(Value::Nil))
+    }
+
+    /// Lists the global variables, sorted by name. Globals that have been declared without a
+    /// value read as nil
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        let mut globals: Vec<_> = self
+            .globals
+            .borrow()
+            .values()
+            .map(|(name, value)| (name.clone(), value.clone().unwrap_or(Value::Nil)))
+            .collect();
+        globals.sort_by(|a, b| a.0.cmp(&b.0));
+        globals
+    }
+
+    /// Runs the statements in order, stopping at the first runtime error. When the last
+    /// statement is an expression statement its value is returned
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<Option<Value>, RoxError> {
+        let mut value = None;
         for statement in statements {
-            self.execute_statement(statement, &mut environments);
+            let result = match statement {
+                Stmt::Expression(expr, _) => self.evaluate_expression(expr).map(Some),
+                _ => self.execute_statement(statement).map(|_| None),
+            };
+
+            match result {
+                Ok(result) => value = result,
+                Err(err) => {
+                    let trace = match self.trace.take() {
+                        Some(trace) => trace,
+                        None => self.stack_trace(&err),
+                    };
+                    self.frames.clear();
+
+                    let mut error = RoxError::from(err);
+                    error.trace = trace;
+                    return Err(error);
+                }
+            }
         }
+        Ok(value)
     }
 
-    fn execute_statement(&mut self, statement: &Stmt, environments: &mut Vec<Environment>) {
+    /// Lists the active calls, innermost first, ending with the top level of the script
+    fn stack_trace(&self, err: &EvaluationError) -> Vec<Frame> {
+        let mut line = err.parts().0.span.line;
+        let mut trace = Vec::new();
+        for frame in self.frames.iter().rev() {
+            trace.push(Frame {
+                function: format!("{}()", frame.function),
+                line,
+            });
+            line = frame.line;
+        }
+        trace.push(Frame {
+            function: "script".to_string(),
+            line,
+        });
+        trace
+    }
+
+    fn execute_statement(&mut self, statement: &Stmt) -> Result<Completion, EvaluationError> {
         match statement {
-            Stmt::Block(statements) => {
-                self.execute_block(statements, environments);
-            }
-            Stmt::Print(expr) => {
-                let result = self.evaluate_expression(expr, environments);
-                match result {
-                    Ok(value) => println!("{}", value.to_string()),
-                    Err(err) => println!("{:?}", err),
+            Stmt::Block(statements, _) => {
+                let environment = Environment::with_enclosing(self.environment.clone());
+                return self.execute_block(statements, environment);
+            }
+            Stmt::Print(expr, _) => {
+                let value = self.evaluate_expression(expr)?;
+                println!("{}", value);
+            }
+            Stmt::Expression(expr, _) => {
+                self.evaluate_expression(expr)?;
+            }
+            Stmt::Class(name, superclass, declarations, _) => {
+                self.execute_class(name, superclass, declarations)?;
+            }
+            Stmt::Function(declaration) => {
+                let function = Function::new(declaration.clone(), self.environment.clone(), false);
+                self.define(
+                    declaration.name.lexeme.clone(),
+                    Some(Value::Function(Rc::new(function))),
+                );
+            }
+            Stmt::If(condition, then_branch, else_branch, _) => {
+                let value = self.evaluate_expression(condition)?;
+                if is_truthy(&value) {
+                    return self.execute_statement(then_branch);
+                } else if let Some(else_branch) = else_branch {
+                    return self.execute_statement(else_branch);
                 }
             }
-            Stmt::Expression(expr) => {
-                let result = self.evaluate_expression(expr, environments);
-                match result {
-                    Ok(_) => {}
-                    Err(err) => println!("{:?}", err),
+            Stmt::Return(_keyword, value, _) => {
+                let value = value
+                    .as_ref()
+                    .map(|expr| self.evaluate_expression(expr))
+                    .transpose()?;
+                return Ok(Completion::Return(value.unwrap_or(Value::Nil)));
+            }
+            Stmt::While(condition, body, _) => {
+                while is_truthy(&self.evaluate_expression(condition)?) {
+                    if let Completion::Return(value) = self.execute_statement(body)? {
+                        return Ok(Completion::Return(value));
+                    }
                 }
             }
-            Stmt::Var(name, initialiser) => {
+            Stmt::Var(name, initialiser, _) => {
                 let value = initialiser
                     .as_ref()
-                    .map(|expr| self.evaluate_expression(expr, environments))
-                    .transpose();
-                match value {
-                    Ok(value) => Interpreter::define(environments, name.lexeme.clone(), value),
-                    Err(err) => println!("{:?}", err),
+                    .map(|expr| self.evaluate_expression(expr))
+                    .transpose()?;
+                self.define(name.lexeme.clone(), value);
+            }
+        }
+
+        Ok(Completion::Normal)
+    }
+
+    fn execute_class(
+        &mut self,
+        name: &Token,
+        superclass: &Option<Expr>,
+        declarations: &[Rc<FunctionDeclaration>],
+    ) -> Result<(), EvaluationError> {
+        let superclass = match superclass {
+            Some(expr @ Expr::Variable(superclass_name, _)) => {
+                match self.evaluate_expression(expr)? {
+                    Value::Class(class) => Some(class),
+                    _ => {
+                        return Err(EvaluationError::InvalidSuperclass(
+                            superclass_name.clone(),
+                            "Superclass must be a class".to_string(),
+                        ))
+                    }
                 }
             }
+            _ => None,
+        };
+
+        // Methods of a subclass close over an extra scope holding 'super'
+        let enclosing = self.environment.clone();
+        if let Some(superclass) = &superclass {
+            let mut environment = Environment::with_enclosing(enclosing.clone());
+            environment.define("super".to_string(), Some(Value::Class(superclass.clone())));
+            self.environment = Rc::new(RefCell::new(environment));
         }
+
+        let mut methods = HashMap::new();
+        for declaration in declarations {
+            let is_initializer = declaration.name.lexeme == "init";
+            let method = Function::new(
+                declaration.clone(),
+                self.environment.clone(),
+                is_initializer,
+            );
+            methods.insert(declaration.name.lexeme.clone(), Rc::new(method));
+        }
+
+        self.environment = enclosing;
+
+        let class = Class::new(name.lexeme.clone(), superclass, methods);
+        self.define(name.lexeme.clone(), Some(Value::Class(Rc::new(class))));
+        Ok(())
     }
 
-    fn execute_block(&mut self, statements: &Vec<Stmt>, environments: &mut Vec<Environment>) {
-        environments.push(Environment::new());
+    /// Executes the statements with the given environment as the current scope. The previous
+    /// scope is always restored, even when a 'return' or an error stops execution early
+    fn execute_block(
+        &mut self,
+        statements: &[Stmt],
+        environment: Environment,
+    ) -> Result<Completion, EvaluationError> {
+        let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(environment)));
+
+        let mut result = Ok(Completion::Normal);
         for statement in statements {
-            self.execute_statement(statement, environments);
+            result = self.execute_statement(statement);
+            if let Ok(Completion::Normal) = result {
+                continue;
+            }
+            break;
         }
-        environments.pop();
+
+        self.environment = previous;
+        result
     }
 
-    fn evaluate_expression(
+    /// Calls the function with a frame on the call stack for it, capturing the stack trace if
+    /// this is where an error starts unwinding
+    fn call_function(
         &mut self,
-        expr: &Expr,
-        environments: &mut Vec<Environment>,
+        function: &Function,
+        arguments: Vec<Value>,
+        paren: &Token,
     ) -> Result<Value, EvaluationError> {
+        if self.frames.len() >= MAX_FRAMES {
+            return Err(EvaluationError::StackOverflow(
+                paren.clone(),
+                "Stack overflow".to_string(),
+            ));
+        }
+
+        self.frames.push(CallFrame {
+            function: function.name().to_string(),
+            line: paren.span.line,
+        });
+
+        let result = self.execute_call(function, arguments);
+        if let Err(err) = &result {
+            if self.trace.is_none() {
+                self.trace = Some(self.stack_trace(err));
+            }
+        }
+
+        self.frames.pop();
+        result
+    }
+
+    fn execute_call(
+        &mut self,
+        function: &Function,
+        arguments: Vec<Value>,
+    ) -> Result<Value, EvaluationError> {
+        let mut environment = Environment::with_enclosing(function.closure.clone());
+        for (param, argument) in function.declaration.params.iter().zip(arguments) {
+            environment.define(param.lexeme.clone(), Some(argument));
+        }
+
+        let completion = self.execute_block(&function.declaration.body, environment)?;
+
+        // Initializers always return the instance, which 'bind' placed in the closure
+        if function.is_initializer {
+            return Ok(function
+                .closure
+                .borrow()
+                .get_at(0, "this")
+                .ok()
+                .flatten()
+                .unwrap_or(Value::Nil));
+        }
+
+        match completion {
+            Completion::Return(value) => Ok(value),
+            Completion::Normal => Ok(Value::Nil),
+        }
+    }
+
+    fn evaluate_expression(&mut self, expr: &Expr) -> Result<Value, EvaluationError> {
         match expr {
-            Expr::Literal(value) => Ok(value.clone()),
-            Expr::Grouping(expr) => self.evaluate_expression(expr, environments),
-            Expr::Unary(operator, expr) => self.evaluate_unary(operator, expr, environments),
-            Expr::Binary(left, operator, right) => {
-                self.evaluate_binary(left, operator, right, environments)
-            }
-            Expr::Variable(name_token) => Interpreter::get(environments, name_token)
-                .map(|value_option| value_option.unwrap_or(Value::Nil))
-                .map_err(|_| EvaluationError::VariableDoesNotExist),
-            Expr::Assign(name_token, expr) => {
-                let result = self.evaluate_expression(expr, environments);
+            Expr::Literal(value, _) => Ok(value.clone()),
+            Expr::Grouping(expr, _) => self.evaluate_expression(expr),
+            Expr::Interpolation(parts, _) => {
+                let mut string = String::new();
+                for part in parts {
+                    match self.evaluate_expression(part)? {
+                        Value::String(part) => string.push_str(&part),
+                        value => string.push_str(&value.to_string()),
+                    }
+                }
+                Ok(Value::String(string))
+            }
+            Expr::Call(callee, paren, arguments) => self.evaluate_call(callee, paren, arguments),
+            Expr::Get(object, name) => match self.evaluate_expression(object)? {
+                Value::Instance(instance) => Instance::get(&instance, name).ok_or_else(|| {
+                    EvaluationError::UndefinedProperty(
+                        name.clone(),
+                        format!("Undefined property '{}'", name.lexeme),
+                    )
+                }),
+                _ => Err(EvaluationError::NotAnInstance(
+                    name.clone(),
+                    "Only instances have properties".to_string(),
+                )),
+            },
+            Expr::Set(object, name, value) => match self.evaluate_expression(object)? {
+                Value::Instance(instance) => {
+                    let value = self.evaluate_expression(value)?;
+                    instance.borrow_mut().set(name, value.clone());
+                    Ok(value)
+                }
+                _ => Err(EvaluationError::NotAnInstance(
+                    name.clone(),
+                    "Only instances have fields".to_string(),
+                )),
+            },
+            Expr::Super(keyword, method, depth) => {
+                self.evaluate_super(keyword, method, depth.get())
+            }
+            Expr::This(keyword, depth) => self
+                .get(keyword, depth.get())
+                .map(|value_option| value_option.unwrap_or(Value::Nil)),
+            Expr::Unary(operator, expr) => self.evaluate_unary(operator, expr),
+            Expr::Binary(left, operator, right) => self.evaluate_binary(left, operator, right),
+            Expr::Logical(left, operator, right) => self.evaluate_logical(left, operator, right),
+            Expr::Variable(name_token, depth) => self
+                .get(name_token, depth.get())
+                .map(|value_option| value_option.unwrap_or(Value::Nil)),
+            Expr::Assign(name_token, expr, depth) => {
+                let result = self.evaluate_expression(expr);
                 result.and_then(|value| {
-                    if Interpreter::assign(environments, name_token, &value) {
+                    if self.assign(name_token, &value, depth.get()) {
                         Ok(value)
                     } else {
-                        Err(EvaluationError::InvalidAssignment)
+                        Err(EvaluationError::InvalidAssignment(
+                            name_token.clone(),
+                            format!("Undefined variable '{}'", name_token.lexeme),
+                        ))
                     }
                 })
             }
         }
     }
 
-    fn evaluate_unary(
-        &mut self,
-        operator: &Token,
-        expr: &Expr,
-        environments: &mut Vec<Environment>,
-    ) -> Result<Value, EvaluationError> {
-        let value = self.evaluate_expression(expr, environments);
+    fn evaluate_unary(&mut self, operator: &Token, expr: &Expr) -> Result<Value, EvaluationError> {
+        let value = self.evaluate_expression(expr);
 
         match (&operator.type_, value) {
+            (&TokenType::Minus, Ok(Value::Int(int))) => {
+                int.checked_neg().map(Value::Int).ok_or_else(|| {
+                    EvaluationError::IntegerOverflow(
+                        operator.clone(),
+                        "Integer overflow".to_string(),
+                    )
+                })
+            }
             (&TokenType::Minus, Ok(Value::Double(double))) => Ok(Value::Double(-double)),
             (&TokenType::Minus, Ok(_)) => Err(EvaluationError::InvalidUnaryOperand(
                 operator.clone(),
@@ -123,121 +492,353 @@ impl Interpreter {
         left: &Expr,
         operator: &Token,
         right: &Expr,
-        environments: &mut Vec<Environment>,
     ) -> Result<Value, EvaluationError> {
-        let left = self.evaluate_expression(left, environments);
-        let right = self.evaluate_expression(right, environments);
+        let left = self.evaluate_expression(left)?;
+        let right = self.evaluate_expression(right)?;
 
-        match (left, &operator.type_, right) {
-            (Ok(Value::Double(left)), &TokenType::Minus, Ok(Value::Double(right))) => {
-                Ok(Value::Double(left - right))
-            }
-            (Ok(_), &TokenType::Minus, Ok(_)) => Err(EvaluationError::InvalidBinaryOperand(
-                operator.clone(),
-                "Operands must be numbers".to_string(),
-            )),
-            (Ok(Value::Double(left)), &TokenType::Slash, Ok(Value::Double(right))) => {
-                Ok(Value::Double(left / right))
+        match (&operator.type_, left, right) {
+            (&TokenType::Plus, Value::String(left), Value::String(right)) => {
+                Ok(Value::String(format!("{}{}", left, right)))
             }
-            (Ok(_), &TokenType::Slash, Ok(_)) => Err(EvaluationError::InvalidBinaryOperand(
-                operator.clone(),
-                "Operands must be numbers".to_string(),
-            )),
-            (Ok(Value::Double(left)), &TokenType::Star, Ok(Value::Double(right))) => {
-                Ok(Value::Double(left * right))
+            (&TokenType::BangEqual, left, right) => Ok(Value::Bool(!is_equal(&left, &right))),
+            (&TokenType::EqualEqual, left, right) => Ok(Value::Bool(is_equal(&left, &right))),
+            (
+                &TokenType::Greater
+                | &TokenType::GreaterEqual
+                | &TokenType::Less
+                | &TokenType::LessEqual,
+                left,
+                right,
+            ) => {
+                let ordering = compare(&left, &right).ok_or_else(|| {
+                    EvaluationError::InvalidBinaryOperand(
+                        operator.clone(),
+                        "Operands must be numbers".to_string(),
+                    )
+                })?;
+                let result = match operator.type_ {
+                    TokenType::Greater => ordering == Some(Ordering::Greater),
+                    TokenType::GreaterEqual => {
+                        matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                    }
+                    TokenType::Less => ordering == Some(Ordering::Less),
+                    _ => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                };
+                Ok(Value::Bool(result))
             }
-            (Ok(_), &TokenType::Star, Ok(_)) => Err(EvaluationError::InvalidBinaryOperand(
-                operator.clone(),
-                "Operands must be numbers".to_string(),
-            )),
-            (Ok(Value::Double(left)), &TokenType::Plus, Ok(Value::Double(right))) => {
-                Ok(Value::Double(left + right))
+            (_, left, right) => match Numbers::of(&left, &right) {
+                Some(Numbers::Ints(left, right)) => evaluate_ints(operator, left, right),
+                Some(Numbers::Doubles(left, right)) => evaluate_doubles(operator, left, right),
+                None if operator.type_ == TokenType::Plus => {
+                    Err(EvaluationError::InvalidBinaryOperand(
+                        operator.clone(),
+                        "Operands must be two numbers or two strings".to_string(),
+                    ))
+                }
+                None => Err(EvaluationError::InvalidBinaryOperand(
+                    operator.clone(),
+                    "Operands must be numbers".to_string(),
+                )),
+            },
+        }
+    }
+
+    fn evaluate_call(
+        &mut self,
+        callee: &Expr,
+        paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<Value, EvaluationError> {
+        let callee = self.evaluate_expression(callee)?;
+
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.evaluate_expression(argument))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let arity = match &callee {
+            Value::Function(function) => function.arity(),
+            Value::NativeFunction(function) => function.arity,
+            Value::Class(class) => class.arity(),
+            _ => {
+                return Err(EvaluationError::NotCallable(
+                    paren.clone(),
+                    "Can only call functions and classes".to_string(),
+                ))
             }
-            (Ok(Value::String(left)), &TokenType::Plus, Ok(Value::String(right))) => {
-                Ok(Value::String(format!("{}{}", left, right)))
+        };
+
+        if arguments.len() != arity {
+            return Err(EvaluationError::ArityMismatch(
+                paren.clone(),
+                format!("Expected {} arguments but got {}", arity, arguments.len()),
+            ));
+        }
+
+        match callee {
+            Value::Function(function) => self.call_function(&function, arguments, paren),
+            Value::NativeFunction(function) => function
+                .call(&arguments)
+                .map_err(|error| EvaluationError::NativeError(paren.clone(), error)),
+            Value::Class(class) => {
+                let instance = Rc::new(RefCell::new(Instance::new(class.clone())));
+                if let Some(initializer) = class.find_method("init") {
+                    self.call_function(&initializer.bind(instance.clone()), arguments, paren)?;
+                }
+                Ok(Value::Instance(instance))
             }
-            (Ok(_), &TokenType::Plus, Ok(_)) => Err(EvaluationError::InvalidBinaryOperand(
-                operator.clone(),
-                "Operands must be two numbers or two strings".to_string(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Finds the method on the superclass and binds it to the current instance. The resolver
+    /// guarantees that 'this' lives in the scope just inside the one holding 'super'
+    fn evaluate_super(
+        &mut self,
+        keyword: &Token,
+        method: &Token,
+        depth: Option<usize>,
+    ) -> Result<Value, EvaluationError> {
+        let distance = depth.unwrap_or(0);
+        let superclass = self.get(keyword, Some(distance))?;
+        let instance = self
+            .environment
+            .borrow()
+            .get_at(distance.saturating_sub(1), "this")
+            .ok()
+            .flatten();
+
+        match (superclass, instance) {
+            (Some(Value::Class(superclass)), Some(Value::Instance(instance))) => superclass
+                .find_method(&method.lexeme)
+                .map(|found| Value::Function(Rc::new(found.bind(instance))))
+                .ok_or_else(|| {
+                    EvaluationError::UndefinedProperty(
+                        method.clone(),
+                        format!("Undefined property '{}'", method.lexeme),
+                    )
+                }),
+            _ => Err(EvaluationError::VariableDoesNotExist(
+                keyword.clone(),
+                "Cannot find superclass or instance".to_string(),
             )),
-            // Greater
-            (Ok(Value::Double(left)), &TokenType::Greater, Ok(Value::Double(right))) => {
-                Ok(Value::Bool(left > right))
+        }
+    }
+
+    /// Evaluates 'and' & 'or' expressions, only evaluating the right hand side when the left
+    /// doesn't decide the result. Returns the deciding operand rather than a bool
+    fn evaluate_logical(
+        &mut self,
+        left: &Expr,
+        operator: &Token,
+        right: &Expr,
+    ) -> Result<Value, EvaluationError> {
+        let left = self.evaluate_expression(left)?;
+
+        match operator.type_ {
+            TokenType::Or if is_truthy(&left) => Ok(left),
+            TokenType::And if !is_truthy(&left) => Ok(left),
+            _ => self.evaluate_expression(right),
+        }
+    }
+
+    fn define(&mut self, name: String, value: Option<Value>) {
+        self.environment.borrow_mut().define(name, value);
+    }
+
+    /// Variables without a resolved depth weren't found in any local scope by the resolver so
+    /// they must be globals
+    fn assign(&mut self, name: &Token, value: &Value, depth: Option<usize>) -> bool {
+        match depth {
+            Some(distance) => {
+                self.environment
+                    .borrow_mut()
+                    .assign_at(distance, &name.lexeme, value)
             }
-            (Ok(_), &TokenType::Greater, Ok(_)) => Err(EvaluationError::InvalidBinaryOperand(
-                operator.clone(),
-                "Operands must be numbers".to_string(),
-            )),
-            // Greater Equal
-            (Ok(Value::Double(left)), &TokenType::GreaterEqual, Ok(Value::Double(right))) => {
-                Ok(Value::Bool(left >= right))
+            None => self.globals.borrow_mut().assign(name, value),
+        }
+    }
+
+    fn get(&self, name: &Token, depth: Option<usize>) -> Result<Option<Value>, EvaluationError> {
+        let result = match depth {
+            Some(distance) => self.environment.borrow().get_at(distance, &name.lexeme),
+            None => self.globals.borrow().get(name),
+        };
+        result.map_err(|_| {
+            EvaluationError::VariableDoesNotExist(
+                name.clone(),
+                format!("Undefined variable '{}'", name.lexeme),
+            )
+        })
+    }
+}
+
+/// The operands of an arithmetic operator. An int is promoted to a double when the other operand
+/// is a double
+enum Numbers {
+    Ints(i64, i64),
+    Doubles(f64, f64),
+}
+
+impl Numbers {
+    fn of(left: &Value, right: &Value) -> Option<Numbers> {
+        match (left, right) {
+            (Value::Int(left), Value::Int(right)) => Some(Numbers::Ints(*left, *right)),
+            (Value::Int(left), Value::Double(right)) => {
+                Some(Numbers::Doubles(*left as f64, *right))
             }
-            (Ok(_), &TokenType::GreaterEqual, Ok(_)) => Err(EvaluationError::InvalidBinaryOperand(
-                operator.clone(),
-                "Operands must be numbers".to_string(),
-            )),
-            // Less
-            (Ok(Value::Double(left)), &TokenType::Less, Ok(Value::Double(right))) => {
-                Ok(Value::Bool(left > right))
+            (Value::Double(left), Value::Int(right)) => {
+                Some(Numbers::Doubles(*left, *right as f64))
             }
-            (Ok(_), &TokenType::Less, Ok(_)) => Err(EvaluationError::InvalidBinaryOperand(
-                operator.clone(),
-                "Operands must be numbers".to_string(),
-            )),
-            // Less Equal
-            (Ok(Value::Double(left)), &TokenType::LessEqual, Ok(Value::Double(right))) => {
-                Ok(Value::Bool(left >= right))
+            (Value::Double(left), Value::Double(right)) => Some(Numbers::Doubles(*left, *right)),
+            _ => None,
+        }
+    }
+}
+
+/// Integer arithmetic raises an error on overflow rather than wrapping. '/' always gives a double,
+/// while '~/' and '%' round towards negative infinity so that the remainder has the sign of the
+/// divisor
+fn evaluate_ints(operator: &Token, left: i64, right: i64) -> Result<Value, EvaluationError> {
+    if right == 0 && matches!(operator.type_, TokenType::TildeSlash | TokenType::Percent) {
+        return Err(EvaluationError::DivisionByZero(
+            operator.clone(),
+            "Division by zero".to_string(),
+        ));
+    }
+
+    let result = match operator.type_ {
+        TokenType::Plus => left.checked_add(right),
+        TokenType::Minus => left.checked_sub(right),
+        TokenType::Star => left.checked_mul(right),
+        TokenType::Slash => return Ok(Value::Double(left as f64 / right as f64)),
+        TokenType::TildeSlash => left.checked_div(right).map(|quotient| {
+            if left % right != 0 && (left < 0) != (right < 0) {
+                quotient - 1
+            } else {
+                quotient
+            }
+        }),
+        TokenType::Percent => {
+            let remainder = left.wrapping_rem(right);
+            if remainder != 0 && (remainder < 0) != (right < 0) {
+                Some(remainder + right)
+            } else {
+                Some(remainder)
             }
-            (Ok(_), &TokenType::LessEqual, Ok(_)) => Err(EvaluationError::InvalidBinaryOperand(
-                operator.clone(),
-                "Operands must be numbers".to_string(),
-            )),
-            (Ok(left), &TokenType::BangEqual, Ok(right)) => Ok(Value::Bool(left != right)),
-            (Ok(left), &TokenType::EqualEqual, Ok(right)) => Ok(Value::Bool(left == right)),
-            _ => Err(EvaluationError::InvalidBinaryOperand(
-                operator.clone(),
-                "Unrecognised binary operation".to_string(),
-            )),
         }
+        _ => return Err(unrecognised_binary(operator)),
+    };
+
+    result.map(Value::Int).ok_or_else(|| {
+        EvaluationError::IntegerOverflow(operator.clone(), "Integer overflow".to_string())
+    })
+}
+
+fn evaluate_doubles(operator: &Token, left: f64, right: f64) -> Result<Value, EvaluationError> {
+    match operator.type_ {
+        TokenType::Plus => Ok(Value::Double(left + right)),
+        TokenType::Minus => Ok(Value::Double(left - right)),
+        TokenType::Star => Ok(Value::Double(left * right)),
+        TokenType::Slash => Ok(Value::Double(left / right)),
+        TokenType::TildeSlash => Ok(Value::Double((left / right).floor())),
+        TokenType::Percent => Ok(Value::Double(left - right * (left / right).floor())),
+        _ => Err(unrecognised_binary(operator)),
     }
+}
 
-    fn define(environments: &mut Vec<Environment>, name: String, value: Option<Value>) {
-        if let Some(last) = environments.last_mut() {
-            last.define(name, value);
+fn unrecognised_binary(operator: &Token) -> EvaluationError {
+    EvaluationError::InvalidBinaryOperand(
+        operator.clone(),
+        "Unrecognised binary operation".to_string(),
+    )
+}
+
+/// Orders two numbers, or gives `None` when either isn't a number. The inner `None` is for NaN,
+/// which isn't ordered against anything
+fn compare(left: &Value, right: &Value) -> Option<Option<Ordering>> {
+    match (left, right) {
+        (Value::Int(left), Value::Int(right)) => Some(Some(left.cmp(right))),
+        (Value::Double(left), Value::Double(right)) => Some(left.partial_cmp(right)),
+        (Value::Int(left), Value::Double(right)) => Some(compare_mixed(*left, *right)),
+        (Value::Double(left), Value::Int(right)) => {
+            Some(compare_mixed(*right, *left).map(Ordering::reverse))
         }
+        _ => None,
     }
+}
 
-    fn assign(environments: &mut Vec<Environment>, name: &Token, value: &Value) -> bool {
-        for environment in environments.iter_mut().rev() {
-            if environment.assign(name, value) {
-                return true;
-            }
+/// Orders an int against a double exactly. Promoting the int instead could round it, making
+/// 9223372036854775807 equal to 9223372036854775806.0
+fn compare_mixed(int: i64, double: f64) -> Option<Ordering> {
+    // 2^63, the smallest double above every i64
+    const INT_LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+    if double.is_nan() {
+        None
+    } else if double >= INT_LIMIT {
+        Some(Ordering::Less)
+    } else if double < -INT_LIMIT {
+        Some(Ordering::Greater)
+    } else {
+        // The whole part fits in an i64, so only the fraction is left to break a tie
+        let whole = double.trunc();
+        match int.cmp(&(whole as i64)) {
+            Ordering::Equal => 0.0_f64.partial_cmp(&(double - whole)),
+            ordering => Some(ordering),
         }
-        false
     }
+}
 
-    fn get(
-        environments: &mut Vec<Environment>,
-        name: &Token,
-    ) -> Result<Option<Value>, EvaluationError> {
-        let mut result = Err(EvaluationError::VariableDoesNotExist);
-        for environment in environments.iter().rev() {
-            result = environment
-                .get(name)
-                .map_err(|_| EvaluationError::VariableDoesNotExist);
-            if let Ok(_) = result {
-                return result;
-            }
+/// Numbers are equal when they have exactly the same value, whether they are ints or doubles
+fn is_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Int(int), Value::Double(double)) | (Value::Double(double), Value::Int(int)) => {
+            compare_mixed(*int, *double) == Some(Ordering::Equal)
         }
-        result
+        _ => left == right,
     }
 }
 
 fn is_truthy(value: &Value) -> bool {
     match value {
         Value::String(_) => true,
+        Value::Int(_) => true,
         Value::Double(_) => true,
         Value::Bool(boolean) => *boolean,
+        Value::Function(_) => true,
+        Value::NativeFunction(_) => true,
+        Value::Class(_) => true,
+        Value::Instance(_) => true,
         Value::Nil => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::resolver::Resolver;
+    use crate::scanner::Scanner;
+
+    fn run(interpreter: &mut Interpreter, source: &str) {
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        let (statements, errors) = Parser::new(tokens).parse();
+        assert!(errors.is_empty());
+        Resolver::new().resolve(&statements).unwrap();
+        interpreter.interpret(&statements).unwrap();
+    }
+
+    #[test]
+    fn dropping_the_interpreter_frees_the_globals() {
+        let mut interpreter = Interpreter::new();
+        run(
+            &mut interpreter,
+            "fun f() { return f; } class A { m() { return this; } } var a = A();",
+        );
+
+        let globals = Rc::downgrade(&interpreter.globals);
+        drop(interpreter);
+        assert!(globals.upgrade().is_none());
+    }
+}
@@ -0,0 +1,173 @@
+//! An interpreter for the Lox language that can be embedded in other Rust programs.
+//!
+//! ```
+//! let mut rox = rox::Rox::new();
+//! rox.set_global("answer", rox::Value::Double(42.0));
+//! rox.eval_str("var doubled = answer * 2;").unwrap();
+//! assert_eq!(rox.get_global("doubled"), Some(rox::Value::Double(84.0)));
+//! ```
+
+// Errors carry everything needed to render them, and are only built once something has gone wrong
+#![allow(clippy::result_large_err)]
+
+mod class;
+mod diagnostic;
+mod environment;
+mod error;
+mod function;
+mod interpreter;
+mod resolver;
+mod value;
+
+// Not part of the embedding API, but the REPL and the benchmarks work with tokens and syntax trees
+#[doc(hidden)]
+pub mod parser;
+#[doc(hidden)]
+pub mod scanner;
+
+use std::io::Read;
+
+pub use crate::diagnostic::Renderer;
+pub use crate::error::ErrorKind;
+pub use crate::error::Frame;
+pub use crate::error::Note;
+pub use crate::error::NoteKind;
+pub use crate::error::RoxError;
+pub use crate::function::NativeError;
+pub use crate::function::NativeFn;
+pub use crate::scanner::Span;
+pub use crate::value::Value;
+
+use crate::interpreter::Interpreter;
+use crate::parser::Expr;
+use crate::parser::Parser;
+use crate::parser::Stmt;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+
+/// A scripting session. Globals defined by one call to `eval_str` or `run_file` are visible to
+/// later calls on the same `Rox`
+pub struct Rox {
+    interpreter: Interpreter,
+}
+
+impl Default for Rox {
+    fn default() -> Rox {
+        Rox::new()
+    }
+}
+
+impl Rox {
+    pub fn new() -> Rox {
+        Rox {
+            interpreter: Interpreter::new(),
+        }
+    }
+
+    /// Runs the source as a script. Every error found before the script starts running is
+    /// returned, whereas running stops at the first runtime error
+    pub fn eval_str(&mut self, source: &str) -> Result<(), Vec<RoxError>> {
+        let tokens = Scanner::new(source.to_string()).scan_tokens()?;
+
+        let (statements, errors) = Parser::new(tokens).parse();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        self.run(&statements).map(|_| ())
+    }
+
+    /// Runs a line of REPL input, which follows on from the input before it, so spans in errors
+    /// start from the given byte offset and line. A lone expression doesn't need a trailing ';'.
+    /// When the last statement is an expression its value is returned to be echoed, unless it is
+    /// nil or the expression is an assignment
+    pub fn eval_line(
+        &mut self,
+        source: &str,
+        offset: usize,
+        line: usize,
+    ) -> Result<Option<Value>, Vec<RoxError>> {
+        let tokens = Scanner::starting_at(source.to_string(), offset, line).scan_tokens()?;
+
+        let (mut statements, errors) = Parser::new(tokens.clone()).parse();
+        if !errors.is_empty() {
+            let expr = Parser::new(tokens).parse_expression().map_err(|_| errors)?;
+            let span = expr.span();
+            statements = vec![Stmt::Expression(expr, span)];
+        }
+
+        let value = self.run(&statements)?;
+        let echoed = matches!(
+            statements.last(),
+            Some(Stmt::Expression(expr, _)) if !matches!(expr, Expr::Assign(..) | Expr::Set(..))
+        );
+        Ok(value.filter(|value| echoed && *value != Value::Nil))
+    }
+
+    /// Parses the source as a single expression without running it
+    pub fn parse_expression(&self, source: &str) -> Result<Expr, Vec<RoxError>> {
+        let tokens = Scanner::new(source.to_string()).scan_tokens()?;
+        Parser::new(tokens).parse_expression()
+    }
+
+    fn run(&mut self, statements: &[Stmt]) -> Result<Option<Value>, Vec<RoxError>> {
+        Resolver::new().resolve(statements)?;
+
+        self.interpreter
+            .interpret(statements)
+            .map_err(|err| vec![err])
+    }
+
+    pub fn run_file(&mut self, path: &str) -> Result<(), Vec<RoxError>> {
+        let contents = read_file(path).map_err(|err| {
+            vec![RoxError::new(
+                ErrorKind::Io,
+                format!("Cannot read '{}': {}", path, err),
+                Span::default(),
+                None,
+            )]
+        })?;
+        self.eval_str(&contents)
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.interpreter.set_global(name, value);
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.interpreter.get_global(name)
+    }
+
+    /// Lists the global variables, sorted by name
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        self.interpreter.globals()
+    }
+
+    /// Makes a Rust function available to scripts as a global with the given name. The function
+    /// can capture whatever it needs from the host, such as configuration or handles
+    ///
+    /// ```
+    /// let prefix = String::from("service.");
+    /// let mut rox = rox::Rox::new();
+    /// rox.define_native("setting", 1, move |arguments| match &arguments[0] {
+    ///     rox::Value::String(name) => Ok(rox::Value::String(format!("{}{}", prefix, name))),
+    ///     _ => Err(rox::NativeError::ArgumentType("Expect a string".to_string())),
+    /// });
+    /// rox.eval_str(r#"var key = setting("port");"#).unwrap();
+    /// assert_eq!(rox.get_global("key"), Some(rox::Value::String("service.port".to_string())));
+    /// ```
+    pub fn define_native<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, NativeError> + 'static,
+    {
+        self.interpreter
+            .define_native(name, arity, std::rc::Rc::new(function));
+    }
+}
+
+fn read_file(path: &str) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    Ok(contents)
+}
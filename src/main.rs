@@ -1,12 +1,7 @@
 use std::cmp::Ordering;
-use std::io::Read;
+use std::io::IsTerminal;
 
-mod environment;
-mod error;
-mod interpreter;
-mod parser;
-mod scanner;
-mod value;
+mod repl;
 
 /*
 let ast = Expr::Binary(
@@ -23,57 +18,69 @@ let ast = Expr::Binary(
 println!("{}", ast.to_string())
 */
 
+// Exit codes from BSD's sysexits.h, as used by the reference Lox implementation
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_NOINPUT: i32 = 66;
+const EX_SOFTWARE: i32 = 70;
+
 fn main() {
     let args: Vec<String> = std::env::args().collect();
     let count = args.len();
     match count.cmp(&2) {
         Ordering::Greater => {
-            println!("Usage: rox [script]");
-            std::process::exit(64);
+            eprintln!("Usage: rox [script]");
+            std::process::exit(EX_USAGE);
         }
         Ordering::Equal => {
             run_file(&args[1]);
         }
         Ordering::Less => {
-            run_prompt();
+            repl::Repl::new().run();
         }
     }
 }
 
-fn run_file(file: &str) {
-    let mut file = std::fs::File::open(file).unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    run(contents);
+/// Reads the script itself rather than using `Rox::run_file`, as the source is needed again to
+/// show snippets of it in any errors
+fn run_file(path: &str) {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            let message = format!("Cannot read '{}': {}", path, error);
+            let error = rox::RoxError::new(rox::ErrorKind::Io, message, Default::default(), None);
+            report(vec![error], "", path);
+            std::process::exit(EX_NOINPUT);
+        }
+    };
 
-    // if (hadError) std::process::exit(65);
+    let mut rox = rox::Rox::new();
+    if let Err(errors) = rox.eval_str(&source) {
+        let code = exit_code(&errors);
+        report(errors, &source, path);
+        std::process::exit(code);
+    }
 }
 
-fn run_prompt() {
-    print!("> ");
-    loop {
-        let mut input = String::new();
-        match std::io::stdin().read_line(&mut input) {
-            Ok(_) => run(input),
-            Err(_error) => {
-                std::process::exit(64);
-            }
-        }
-        // hadError = false;
+/// The exit code called for by the errors from running a script
+fn exit_code(errors: &[rox::RoxError]) -> i32 {
+    if errors.iter().any(|error| error.kind == rox::ErrorKind::Io) {
+        EX_NOINPUT
+    } else if errors
+        .iter()
+        .any(|error| error.kind == rox::ErrorKind::Runtime)
+    {
+        EX_SOFTWARE
+    } else {
+        EX_DATAERR
     }
 }
 
-fn run(source: String) {
-    let mut scanner = scanner::Scanner::new(source);
-    let tokens = scanner.scan_tokens();
-    // println!("tokens: {:?}", tokens);
-    let mut parser = parser::Parser::new(tokens);
-
-    match parser.parse() {
-        Ok(statements) => {
-            let mut interpreter = interpreter::Interpreter::new();
-            interpreter.interpret(&statements);
-        }
-        Err(err) => println!("{:?}", err),
+/// Prints the errors to stderr, in color when it's a terminal and NO_COLOR isn't set
+fn report(errors: Vec<rox::RoxError>, source: &str, path: &str) {
+    let color = std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let renderer = rox::Renderer::new(color);
+    for error in errors {
+        eprintln!("{}", renderer.render(&error, source, path));
     }
 }
@@ -1,144 +1,440 @@
-use crate::error;
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::error::ErrorKind;
+use crate::error::RoxError;
 use crate::scanner;
+use crate::scanner::Span;
 use crate::scanner::Token;
 use crate::scanner::TokenType;
 use crate::value::Value;
 
-pub fn token_error(token: &Token, message: &String) {
-    if token.type_ == TokenType::Eof {
-        error::report(token.line, " at end", message);
-    } else {
-        let string = format!(" at '{}'", token.lexeme);
-        error::report(token.line, &string, message);
+/// Each statement, apart from functions which keep it in their declaration, records the span of
+/// source it was parsed from
+#[derive(Debug)]
+pub enum Stmt {
+    Block(Vec<Stmt>, Span),
+    Class(Token, Option<Expr>, Vec<Rc<FunctionDeclaration>>, Span),
+    Expression(Expr, Span),
+    Function(Rc<FunctionDeclaration>),
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>, Span),
+    Print(Expr, Span),
+    Return(Token, Option<Expr>, Span),
+    Var(Token, Option<Expr>, Span),
+    While(Expr, Box<Stmt>, Span),
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Block(_, span)
+            | Stmt::Class(_, _, _, span)
+            | Stmt::Expression(_, span)
+            | Stmt::If(_, _, _, span)
+            | Stmt::Print(_, span)
+            | Stmt::Return(_, _, span)
+            | Stmt::Var(_, _, span)
+            | Stmt::While(_, _, span) => *span,
+            Stmt::Function(declaration) => declaration.span,
+        }
     }
 }
 
-pub enum Stmt {
-    Block(Vec<Stmt>),
-    Expression(Expr),
-    Print(Expr),
-    Var(Token, Option<Expr>),
+/// Shared between the statement tree and any function values created from it so that the body
+/// doesn't need to be cloned each time the declaration is executed
+#[derive(Debug)]
+pub struct FunctionDeclaration {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<Stmt>,
+    pub span: Span,
 }
 
+/// The number of scopes between a variable reference and the scope it was declared in. Filled in
+/// by the resolver and left as `None` for globals
+pub type Depth = Cell<Option<usize>>;
+
+#[derive(Debug)]
 pub enum Expr {
     Binary(Box<Expr>, Token, Box<Expr>),
-    Grouping(Box<Expr>),
-    Literal(Value),
+    Call(Box<Expr>, Token, Vec<Expr>),
+    Get(Box<Expr>, Token),
+    Grouping(Box<Expr>, Span),
+    /// The parts of an interpolated string, which are joined together as strings
+    Interpolation(Vec<Expr>, Span),
+    Literal(Value, Span),
+    Logical(Box<Expr>, Token, Box<Expr>),
+    Set(Box<Expr>, Token, Box<Expr>),
+    Super(Token, Token, Depth),
+    This(Token, Depth),
     Unary(Token, Box<Expr>),
-    Variable(Token),
-    Assign(Token, Box<Expr>),
+    Variable(Token, Depth),
+    Assign(Token, Box<Expr>, Depth),
+}
+
+impl Expr {
+    /// The span of source the expression was parsed from. Only literals and groupings need to
+    /// store their span, the rest can be worked out from their tokens and sub-expressions
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+                left.span().to(right.span())
+            }
+            Expr::Call(callee, paren, _) => callee.span().to(paren.span),
+            Expr::Get(object, name) => object.span().to(name.span),
+            Expr::Grouping(_, span) | Expr::Interpolation(_, span) | Expr::Literal(_, span) => {
+                *span
+            }
+            Expr::Set(object, _, value) => object.span().to(value.span()),
+            Expr::Super(keyword, method, _) => keyword.span.to(method.span),
+            Expr::This(keyword, _) => keyword.span,
+            Expr::Unary(operator, right) => operator.span.to(right.span()),
+            Expr::Variable(name, _) => name.span,
+            Expr::Assign(name, value, _) => name.span.to(value.span()),
+        }
+    }
 }
 
 // Printer
 
-impl std::string::ToString for Expr {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Expr::Binary(left, operator, right) => format!(
-                "({} {} {})",
-                operator.type_.to_string(),
-                left.to_string(),
-                right.to_string()
-            ),
-            Expr::Grouping(inner_expr) => format!("(group {})", inner_expr.to_string()),
-            Expr::Literal(value) => match value {
-                Value::String(string) => format!("\"{}\"", string.clone()),
-                Value::Double(double) => double.to_string(),
-                Value::Bool(boolean) => boolean.to_string(),
-                Value::Nil => "nil".to_string(),
-            },
-            Expr::Unary(operator, inner_expr) => format!(
-                "({} {})",
-                operator.type_.to_string(),
-                inner_expr.to_string()
-            ),
-            Expr::Variable(name_token) => format!("{}", name_token.lexeme),
-            Expr::Assign(name_token, expr) => {
-                format!("{} = {}", name_token.lexeme, expr.to_string())
+            Expr::Binary(left, operator, right) => {
+                write!(f, "({} {} {})", operator.type_, left, right)
             }
+            Expr::Call(callee, _paren, arguments) => {
+                write!(f, "(call {}", callee)?;
+                for argument in arguments {
+                    write!(f, " {}", argument)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Get(object, name) => write!(f, "{}.{}", object, name.lexeme),
+            Expr::Grouping(inner_expr, _) => write!(f, "(group {})", inner_expr),
+            Expr::Interpolation(parts, _) => {
+                write!(f, "(interpolate")?;
+                for part in parts {
+                    write!(f, " {}", part)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Literal(value, _) => write!(f, "{}", value),
+            Expr::Logical(left, operator, right) => {
+                write!(f, "({} {} {})", operator.lexeme, left, right)
+            }
+            Expr::Set(object, name, value) => {
+                write!(f, "{}.{} = {}", object, name.lexeme, value)
+            }
+            Expr::Super(_keyword, method, _) => write!(f, "super.{}", method.lexeme),
+            Expr::This(_keyword, _) => write!(f, "this"),
+            Expr::Unary(operator, inner_expr) => write!(f, "({} {})", operator.type_, inner_expr),
+            Expr::Variable(name_token, _) => write!(f, "{}", name_token.lexeme),
+            Expr::Assign(name_token, expr, _) => write!(f, "{} = {}", name_token.lexeme, expr),
         }
     }
 }
 
-#[derive(Debug)]
-pub enum ParserError {
-    UnmatchedPrimary,
-    UnexpectedTokenError,
-    InvalidAssignment,
-}
+const MAX_ARGUMENTS: usize = 255;
 
 pub struct Parser {
     tokens: scanner::TokenVec,
     current: usize,
+    errors: Vec<RoxError>,
 }
 
 impl Parser {
     pub fn new(tokens: scanner::TokenVec) -> Parser {
-        Parser { tokens, current: 0 }
+        Parser {
+            tokens,
+            current: 0,
+            errors: Vec::new(),
+        }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, ParserError> {
+    /// Parses the whole token stream. After an error the parser skips ahead to the next likely
+    /// statement boundary and carries on, so every error in the input is returned along with the
+    /// statements that did parse
+    pub fn parse(&mut self) -> (Vec<Stmt>, Vec<RoxError>) {
         let mut statements = Vec::new();
 
         while !self.is_at_end() {
-            match self.declaration() {
-                Ok(statement) => statements.push(statement),
-                Err(err) => return Err(err),
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
             }
         }
 
-        Ok(statements)
+        (statements, std::mem::take(&mut self.errors))
+    }
+
+    /// Parses the tokens as a single expression, with nothing following it
+    pub fn parse_expression(&mut self) -> Result<Expr, Vec<RoxError>> {
+        let result = self.expression().and_then(|expr| {
+            if self.is_at_end() {
+                Ok(expr)
+            } else {
+                Err(self.error(&self.peek(), "Expect end of expression"))
+            }
+        });
+
+        match result {
+            Ok(expr) if self.errors.is_empty() => Ok(expr),
+            Ok(_) => Err(std::mem::take(&mut self.errors)),
+            Err(err) => {
+                self.errors.push(err);
+                Err(std::mem::take(&mut self.errors))
+            }
+        }
     }
 
-    fn declaration(&mut self) -> Result<Stmt, ParserError> {
-        let result = if self.match_(&vec![TokenType::Var]) {
+    fn declaration(&mut self) -> Option<Stmt> {
+        let result = if self.match_(&vec![TokenType::Class]) {
+            self.class_declaration()
+        } else if self.match_(&vec![TokenType::Fun]) {
+            self.function("function").map(Stmt::Function)
+        } else if self.match_(&vec![TokenType::Var]) {
             self.var_declaration()
         } else {
             self.statement()
         };
 
         match result {
-            Ok(_) => result,
-            Err(_) => {
+            Ok(statement) => Some(statement),
+            Err(err) => {
+                self.errors.push(err);
                 self.synchronize();
-                result
+                None
+            }
+        }
+    }
+
+    fn class_declaration(&mut self) -> Result<Stmt, RoxError> {
+        let start = self.previous().span;
+        let name = self.consume(&TokenType::Identifier, "Expect class name".to_string())?;
+
+        let superclass = if self.match_(&vec![TokenType::Less]) {
+            let superclass_name =
+                self.consume(&TokenType::Identifier, "Expect superclass name".to_string())?;
+            Some(Expr::Variable(superclass_name, Depth::default()))
+        } else {
+            None
+        };
+
+        self.consume(
+            &TokenType::LeftBrace,
+            "Expect '{' before class body".to_string(),
+        )?;
+
+        let mut methods = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            methods.push(self.function("method")?);
+        }
+
+        self.consume(
+            &TokenType::RightBrace,
+            "Expect '}' after class body".to_string(),
+        )?;
+
+        Ok(Stmt::Class(
+            name,
+            superclass,
+            methods,
+            self.span_from(start),
+        ))
+    }
+
+    fn function(&mut self, kind: &str) -> Result<Rc<FunctionDeclaration>, RoxError> {
+        // Methods don't have a 'fun' keyword to start from
+        let keyword = self.previous();
+        let name = self.consume(&TokenType::Identifier, format!("Expect {} name", kind))?;
+        let start = if keyword.type_ == TokenType::Fun {
+            keyword.span
+        } else {
+            name.span
+        };
+
+        self.consume(
+            &TokenType::LeftParen,
+            format!("Expect '(' after {} name", kind),
+        )?;
+
+        let mut params = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if params.len() >= MAX_ARGUMENTS {
+                    let err = self.error(&self.peek(), "Cannot have more than 255 parameters");
+                    self.errors.push(err);
+                }
+                params.push(
+                    self.consume(&TokenType::Identifier, "Expect parameter name".to_string())?,
+                );
+
+                if !self.match_(&vec![TokenType::Comma]) {
+                    break;
+                }
             }
         }
+        self.consume(
+            &TokenType::RightParen,
+            "Expect ')' after parameters".to_string(),
+        )?;
+
+        self.consume(
+            &TokenType::LeftBrace,
+            format!("Expect '{{' before {} body", kind),
+        )?;
+        let body = self.block()?;
+
+        Ok(Rc::new(FunctionDeclaration {
+            name,
+            params,
+            body,
+            span: self.span_from(start),
+        }))
     }
 
-    fn var_declaration(&mut self) -> Result<Stmt, ParserError> {
-        let name = self.consume(&TokenType::Identifier, "Expect variable name".to_string());
+    fn var_declaration(&mut self) -> Result<Stmt, RoxError> {
+        let start = self.previous().span;
+        let name = self.consume(&TokenType::Identifier, "Expect variable name".to_string())?;
 
-        let mut initializer = Ok(None);
+        let mut initializer = None;
         if self.match_(&vec![TokenType::Equal]) {
-            initializer = self.expression().map(Some);
+            initializer = Some(self.expression()?);
         }
 
-        let consume_result = self.consume(
+        self.consume(
             &TokenType::Semicolon,
             "Expect ';' after variable declaration".to_string(),
-        );
+        )?;
 
-        result_map3(name, initializer, consume_result, |n, i, _| Stmt::Var(n, i))
+        Ok(Stmt::Var(name, initializer, self.span_from(start)))
     }
 
-    fn statement(&mut self) -> Result<Stmt, ParserError> {
-        if self.match_(&vec![TokenType::Print]) {
+    fn statement(&mut self) -> Result<Stmt, RoxError> {
+        if self.match_(&vec![TokenType::For]) {
+            self.for_statement()
+        } else if self.match_(&vec![TokenType::If]) {
+            self.if_statement()
+        } else if self.match_(&vec![TokenType::Print]) {
             self.print_statement()
+        } else if self.match_(&vec![TokenType::Return]) {
+            self.return_statement()
+        } else if self.match_(&vec![TokenType::While]) {
+            self.while_statement()
         } else if self.match_(&vec![TokenType::LeftBrace]) {
-            self.block().map(|statements| Stmt::Block(statements))
+            let start = self.previous().span;
+            let statements = self.block()?;
+            Ok(Stmt::Block(statements, self.span_from(start)))
         } else {
             self.expression_statement()
         }
     }
 
-    fn block(&mut self) -> Result<Vec<Stmt>, ParserError> {
+    /// Parses a 'for' loop and desugars it into a 'while' loop wrapped in blocks for the
+    /// initializer and increment clauses
+    fn for_statement(&mut self) -> Result<Stmt, RoxError> {
+        let start = self.previous().span;
+        self.consume(&TokenType::LeftParen, "Expect '(' after 'for'".to_string())?;
+
+        let initializer = if self.match_(&vec![TokenType::Semicolon]) {
+            None
+        } else if self.match_(&vec![TokenType::Var]) {
+            Some(self.var_declaration()?)
+        } else {
+            Some(self.expression_statement()?)
+        };
+
+        let condition = if self.check(&TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(
+            &TokenType::Semicolon,
+            "Expect ';' after loop condition".to_string(),
+        )?;
+
+        let increment = if self.check(&TokenType::RightParen) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(
+            &TokenType::RightParen,
+            "Expect ')' after for clauses".to_string(),
+        )?;
+
+        let mut body = self.statement()?;
+        let span = self.span_from(start);
+
+        if let Some(increment) = increment {
+            let increment_span = increment.span();
+            body = Stmt::Block(
+                vec![body, Stmt::Expression(increment, increment_span)],
+                span,
+            );
+        }
+
+        let condition = condition.unwrap_or(Expr::Literal(Value::Bool(true), start));
+        body = Stmt::While(condition, Box::new(body), span);
+
+        if let Some(initializer) = initializer {
+            body = Stmt::Block(vec![initializer, body], span);
+        }
+
+        Ok(body)
+    }
+
+    fn if_statement(&mut self) -> Result<Stmt, RoxError> {
+        let start = self.previous().span;
+        self.consume(&TokenType::LeftParen, "Expect '(' after 'if'".to_string())?;
+        let condition = self.expression()?;
+        self.consume(
+            &TokenType::RightParen,
+            "Expect ')' after if condition".to_string(),
+        )?;
+
+        let then_branch = self.statement()?;
+        let else_branch = if self.match_(&vec![TokenType::Else]) {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+
+        Ok(Stmt::If(
+            condition,
+            Box::new(then_branch),
+            else_branch,
+            self.span_from(start),
+        ))
+    }
+
+    fn while_statement(&mut self) -> Result<Stmt, RoxError> {
+        let start = self.previous().span;
+        self.consume(
+            &TokenType::LeftParen,
+            "Expect '(' after 'while'".to_string(),
+        )?;
+        let condition = self.expression()?;
+        self.consume(
+            &TokenType::RightParen,
+            "Expect ')' after condition".to_string(),
+        )?;
+        let body = self.statement()?;
+
+        Ok(Stmt::While(
+            condition,
+            Box::new(body),
+            self.span_from(start),
+        ))
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, RoxError> {
         let mut statements = Vec::new();
 
         while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
-            match self.declaration() {
-                Ok(statement) => statements.push(statement),
-                Err(err) => return Err(err),
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
             }
         }
 
@@ -147,36 +443,63 @@ impl Parser {
         result.map(|_| statements)
     }
 
-    fn print_statement(&mut self) -> Result<Stmt, ParserError> {
-        let value = self.expression();
-        let result = self.consume(&TokenType::Semicolon, "Expect ';' after value".to_string());
+    fn print_statement(&mut self) -> Result<Stmt, RoxError> {
+        let start = self.previous().span;
+        let value = self.expression()?;
+        self.consume(&TokenType::Semicolon, "Expect ';' after value".to_string())?;
 
-        result_map2(value, result, |value, _| Stmt::Print(value))
+        Ok(Stmt::Print(value, self.span_from(start)))
     }
 
-    fn expression_statement(&mut self) -> Result<Stmt, ParserError> {
-        let value = self.expression();
-        let result = self.consume(&TokenType::Semicolon, "Expect ';' after value".to_string());
+    fn return_statement(&mut self) -> Result<Stmt, RoxError> {
+        let keyword = self.previous();
+        let value = if self.check(&TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(
+            &TokenType::Semicolon,
+            "Expect ';' after return value".to_string(),
+        )?;
 
-        result_map2(value, result, |value, _| Stmt::Expression(value))
+        let span = self.span_from(keyword.span);
+        Ok(Stmt::Return(keyword, value, span))
     }
 
-    fn expression(&mut self) -> Result<Expr, ParserError> {
+    fn expression_statement(&mut self) -> Result<Stmt, RoxError> {
+        let value = self.expression()?;
+        self.consume(&TokenType::Semicolon, "Expect ';' after value".to_string())?;
+
+        let span = self.span_from(value.span());
+        Ok(Stmt::Expression(value, span))
+    }
+
+    fn expression(&mut self) -> Result<Expr, RoxError> {
         self.assignment()
     }
 
-    fn assignment(&mut self) -> Result<Expr, ParserError> {
-        let expr = self.equality();
+    fn assignment(&mut self) -> Result<Expr, RoxError> {
+        let expr = self.or();
 
         if self.match_(&vec![TokenType::Equal]) {
-            let _equals = self.previous();
+            let equals = self.previous();
             let value = self.assignment();
 
             return match expr {
-                Ok(Expr::Variable(name_token)) => {
-                    value.map(|value| Expr::Assign(name_token, Box::new(value)))
+                Ok(Expr::Variable(name_token, _)) => {
+                    value.map(|value| Expr::Assign(name_token, Box::new(value), Depth::default()))
+                }
+                Ok(Expr::Get(object, name_token)) => {
+                    value.map(|value| Expr::Set(object, name_token, Box::new(value)))
+                }
+                // The parser isn't confused about where it is so report the error and carry on
+                // rather than synchronizing
+                Ok(target) => {
+                    let err = self.error(&equals, "Invalid assignment target");
+                    self.errors.push(err);
+                    Ok(target)
                 }
-                Ok(_) => Err(ParserError::InvalidAssignment),
                 err @ Err(_) => err,
             };
         }
@@ -184,7 +507,35 @@ impl Parser {
         expr
     }
 
-    fn equality(&mut self) -> Result<Expr, ParserError> {
+    fn or(&mut self) -> Result<Expr, RoxError> {
+        let mut expr = self.and();
+
+        while self.match_(&vec![TokenType::Or]) {
+            let operator = self.previous();
+            let right = self.and();
+            expr = result_map2(expr, right, |l, r| {
+                Expr::Logical(Box::new(l), operator, Box::new(r))
+            });
+        }
+
+        expr
+    }
+
+    fn and(&mut self) -> Result<Expr, RoxError> {
+        let mut expr = self.equality();
+
+        while self.match_(&vec![TokenType::And]) {
+            let operator = self.previous();
+            let right = self.equality();
+            expr = result_map2(expr, right, |l, r| {
+                Expr::Logical(Box::new(l), operator, Box::new(r))
+            });
+        }
+
+        expr
+    }
+
+    fn equality(&mut self) -> Result<Expr, RoxError> {
         let mut expr = self.comparison();
 
         while self.match_(&vec![TokenType::BangEqual, TokenType::EqualEqual]) {
@@ -198,7 +549,7 @@ impl Parser {
         expr
     }
 
-    fn comparison(&mut self) -> Result<Expr, ParserError> {
+    fn comparison(&mut self) -> Result<Expr, RoxError> {
         let mut expr = self.addition();
 
         let tokens = vec![
@@ -218,7 +569,7 @@ impl Parser {
         expr
     }
 
-    fn addition(&mut self) -> Result<Expr, ParserError> {
+    fn addition(&mut self) -> Result<Expr, RoxError> {
         let mut expr = self.multiplication();
 
         let tokens = vec![TokenType::Minus, TokenType::Plus];
@@ -233,10 +584,15 @@ impl Parser {
         expr
     }
 
-    fn multiplication(&mut self) -> Result<Expr, ParserError> {
+    fn multiplication(&mut self) -> Result<Expr, RoxError> {
         let mut expr = self.unary();
 
-        let tokens = vec![TokenType::Slash, TokenType::Star];
+        let tokens = vec![
+            TokenType::Slash,
+            TokenType::Star,
+            TokenType::TildeSlash,
+            TokenType::Percent,
+        ];
         while self.match_(&tokens) {
             let operator = self.previous();
             let right = self.unary();
@@ -248,56 +604,149 @@ impl Parser {
         expr
     }
 
-    fn unary(&mut self) -> Result<Expr, ParserError> {
+    fn unary(&mut self) -> Result<Expr, RoxError> {
         let tokens = vec![TokenType::Bang, TokenType::Minus];
-        while self.match_(&tokens) {
+        if self.match_(&tokens) {
             let operator = self.previous();
             let right = self.unary();
             return right.map(|r| Expr::Unary(operator, Box::new(r)));
         }
 
-        self.primary()
+        self.call()
     }
 
-    fn primary(&mut self) -> Result<Expr, ParserError> {
+    fn call(&mut self) -> Result<Expr, RoxError> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.match_(&vec![TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_(&vec![TokenType::Dot]) {
+                let name = self.consume(
+                    &TokenType::Identifier,
+                    "Expect property name after '.'".to_string(),
+                )?;
+                expr = Expr::Get(Box::new(expr), name);
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, RoxError> {
+        let mut arguments = Vec::new();
+
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if arguments.len() >= MAX_ARGUMENTS {
+                    let err = self.error(&self.peek(), "Cannot have more than 255 arguments");
+                    self.errors.push(err);
+                }
+                arguments.push(self.expression()?);
+
+                if !self.match_(&vec![TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        let paren = self.consume(
+            &TokenType::RightParen,
+            "Expect ')' after arguments".to_string(),
+        )?;
+
+        Ok(Expr::Call(Box::new(callee), paren, arguments))
+    }
+
+    fn primary(&mut self) -> Result<Expr, RoxError> {
         if self.match_(&vec![TokenType::False]) {
-            return Ok(Expr::Literal(Value::Bool(false)));
+            return Ok(Expr::Literal(Value::Bool(false), self.previous().span));
         }
         if self.match_(&vec![TokenType::True]) {
-            return Ok(Expr::Literal(Value::Bool(true)));
+            return Ok(Expr::Literal(Value::Bool(true), self.previous().span));
         }
         if self.match_(&vec![TokenType::Nil]) {
-            return Ok(Expr::Literal(Value::Nil));
+            return Ok(Expr::Literal(Value::Nil, self.previous().span));
         }
         if self.match_(&vec![TokenType::Number, TokenType::String]) {
-            return Ok(Expr::Literal(self.previous().literal.unwrap()));
+            let token = self.previous();
+            return Ok(Expr::Literal(token.literal.unwrap(), token.span));
+        }
+        if self.match_(&vec![TokenType::InterpolationStart]) {
+            return self.interpolation();
+        }
+        if self.match_(&vec![TokenType::Super]) {
+            let keyword = self.previous();
+            self.consume(&TokenType::Dot, "Expect '.' after 'super'".to_string())?;
+            let method = self.consume(
+                &TokenType::Identifier,
+                "Expect superclass method name".to_string(),
+            )?;
+            return Ok(Expr::Super(keyword, method, Depth::default()));
+        }
+        if self.match_(&vec![TokenType::This]) {
+            return Ok(Expr::This(self.previous(), Depth::default()));
         }
         if self.match_(&vec![TokenType::Identifier]) {
-            return Ok(Expr::Variable(self.previous()));
+            return Ok(Expr::Variable(self.previous(), Depth::default()));
         }
         if self.match_(&vec![TokenType::LeftParen]) {
+            let start = self.previous().span;
             let expr = self.expression();
             let result = self.consume(
                 &TokenType::RightParen,
                 "Expect ')' after expression".to_string(),
             );
-            return result_map2(expr, result, |expr, _| Expr::Grouping(Box::new(expr)));
+            return result_map2(expr, result, |expr, paren| {
+                Expr::Grouping(Box::new(expr), start.to(paren.span))
+            });
+        }
+
+        Err(self.error(&self.peek(), "Expect expression"))
+    }
+
+    /// Parses an interpolated string, starting from the text before its first `${`. The scanner
+    /// splits the string into the text before, between and after the expressions
+    fn interpolation(&mut self) -> Result<Expr, RoxError> {
+        let start = self.previous().span;
+        let mut parts = Vec::new();
+
+        loop {
+            let text = self.previous();
+            parts.push(Expr::Literal(text.literal.unwrap(), text.span));
+            parts.push(self.expression()?);
+
+            if !self.match_(&vec![TokenType::InterpolationMiddle]) {
+                break;
+            }
         }
 
-        Err(ParserError::UnmatchedPrimary)
+        let end = self.consume(
+            &TokenType::InterpolationEnd,
+            "Expect '}' after interpolated expression".to_string(),
+        )?;
+        parts.push(Expr::Literal(end.literal.unwrap(), end.span));
+
+        Ok(Expr::Interpolation(parts, start.to(end.span)))
+    }
+
+    /// The span from `start` through to the last token consumed
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous().span)
     }
 
-    fn consume(&mut self, type_: &TokenType, message: String) -> Result<Token, ParserError> {
+    fn consume(&mut self, type_: &TokenType, message: String) -> Result<Token, RoxError> {
         if self.check(type_) {
             return Ok(self.advance());
         }
 
-        self.error(&self.peek(), &message);
-        Err(ParserError::UnexpectedTokenError)
+        Err(self.error(&self.peek(), &message))
     }
 
-    fn error(&self, token: &Token, message: &String) {
-        token_error(token, message);
+    fn error(&self, token: &Token, message: &str) -> RoxError {
+        RoxError::at_token(ErrorKind::Parse, token, message)
     }
 
     fn match_(&mut self, token_types: &Vec<TokenType>) -> bool {
@@ -329,11 +778,11 @@ impl Parser {
     }
 
     fn peek(&self) -> Token {
-        self.tokens.iter().nth(self.current).unwrap().clone()
+        self.tokens[self.current].clone()
     }
 
     fn previous(&self) -> Token {
-        self.tokens.iter().nth(self.current - 1).unwrap().clone()
+        self.tokens[self.current - 1].clone()
     }
 
     fn synchronize(&mut self) {
@@ -369,15 +818,6 @@ fn result_map2<T, S, O, E, F: FnOnce(T, S) -> O>(
     a.and_then(|a| b.map(|b| op(a, b)))
 }
 
-fn result_map3<T, U, V, O, E, F: FnOnce(T, U, V) -> O>(
-    a: Result<T, E>,
-    b: Result<U, E>,
-    c: Result<V, E>,
-    op: F,
-) -> Result<O, E> {
-    a.and_then(|a| b.and_then(|b| c.map(|c| op(a, b, c))))
-}
-
 /*
 fn result_map4<T, U, V, W, O, E, F: FnOnce(T, U, V, W) -> O>(
     a: Result<T, E>,
@@ -389,3 +829,22 @@ fn result_map4<T, U, V, W, O, E, F: FnOnce(T, U, V, W) -> O>(
     a.and_then(|a| b.and_then(|b| c.and_then(|c| d.map(|d| op(a, b, c, d)))))
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    #[test]
+    fn statements_after_an_error_are_still_parsed() {
+        let source = "var a = ;\nprint 1;\nvar = 2;\nprint 2;\nfun f( {}\nprint 3;";
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        let (statements, errors) = Parser::new(tokens).parse();
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(statements.len(), 3);
+        assert!(statements
+            .iter()
+            .all(|statement| matches!(statement, Stmt::Print(..))));
+    }
+}
@@ -0,0 +1,280 @@
+use std::path::PathBuf;
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::Context;
+use rustyline::Editor;
+use rustyline::Helper;
+
+use rox::scanner::Scanner;
+use rox::ErrorKind;
+use rox::Rox;
+use rox::RoxError;
+
+use crate::report;
+use crate::EX_NOINPUT;
+
+const HELP: &str = "\
+:env          list the global variables
+:ast <expr>   show how an expression is parsed
+:tokens <src> show the tokens in some source
+:load <file>  run a file in this session
+:reset        forget everything defined so far
+:quit         leave the REPL
+:help         show this list";
+
+const COMMANDS: [&str; 7] = [
+    ":env", ":ast", ":tokens", ":load", ":reset", ":quit", ":help",
+];
+
+/// Completes the word before the cursor with a keyword or the name of a global, or a
+/// meta-command at the start of the line
+struct RoxHelper {
+    /// Updated before each line is read, since globals can be defined by any line
+    names: Vec<String>,
+}
+
+impl Completer for RoxHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let before = &line[..pos];
+        let start = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !(c.is_alphanumeric() || *c == '_'))
+            .map_or(0, |(index, c)| index + c.len_utf8());
+        let word = &before[start..];
+
+        if before.starts_with(':') && !before.contains(char::is_whitespace) {
+            let commands = COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(before));
+            return Ok((0, commands.map(|command| command.to_string()).collect()));
+        }
+
+        if word.is_empty() {
+            return Ok((pos, Vec::new()));
+        }
+
+        let names = self.names.iter().filter(|name| name.starts_with(word));
+        Ok((start, names.cloned().collect()))
+    }
+}
+
+impl Hinter for RoxHelper {
+    type Hint = String;
+}
+
+impl Highlighter for RoxHelper {}
+
+impl Validator for RoxHelper {}
+
+impl Helper for RoxHelper {}
+
+/// An interactive session. Everything defined stays around for later lines until `:reset`
+pub struct Repl {
+    rox: Rox,
+
+    /// Everything typed so far, so that errors can show lines entered earlier in the session
+    transcript: String,
+    line: usize,
+
+    /// Lines that don't make a complete statement yet, along with where they start
+    pending: String,
+    pending_offset: usize,
+    pending_line: usize,
+}
+
+impl Repl {
+    pub fn new() -> Repl {
+        Repl {
+            rox: Rox::new(),
+            transcript: String::new(),
+            line: 1,
+            pending: String::new(),
+            pending_offset: 0,
+            pending_line: 1,
+        }
+    }
+
+    /// Reads lines with editing, reverse search (Ctrl-R) and history that is kept between
+    /// sessions in ~/.rox_history
+    pub fn run(&mut self) {
+        let mut editor: Editor<RoxHelper, DefaultHistory> = match Editor::new() {
+            Ok(editor) => editor,
+            Err(error) => {
+                eprintln!("Error: Cannot start the REPL: {}", error);
+                std::process::exit(EX_NOINPUT);
+            }
+        };
+        editor.set_helper(Some(RoxHelper { names: Vec::new() }));
+
+        let history = history_path();
+        if let Some(path) = &history {
+            // There won't be any history the first time
+            let _ = editor.load_history(path);
+        }
+
+        loop {
+            if let Some(helper) = editor.helper_mut() {
+                helper.names = self.completions();
+            }
+
+            match editor.readline(self.prompt()) {
+                Ok(line) => {
+                    let _ = editor.add_history_entry(line.as_str());
+                    if !self.handle(&format!("{}\n", line)) {
+                        break;
+                    }
+                }
+                // Ctrl-C throws away an unfinished statement
+                Err(ReadlineError::Interrupted) => self.pending.clear(),
+                Err(ReadlineError::Eof) => break,
+                Err(error) => {
+                    eprintln!("Error: Cannot read input: {}", error);
+                    std::process::exit(EX_NOINPUT);
+                }
+            }
+        }
+
+        if let Some(path) = &history {
+            if let Err(error) = editor.save_history(path) {
+                eprintln!(
+                    "Error: Cannot save history to '{}': {}",
+                    path.display(),
+                    error
+                );
+            }
+        }
+    }
+
+    /// The keywords and the names of the globals, sorted for showing as completions
+    fn completions(&self) -> Vec<String> {
+        let mut names: Vec<String> = Scanner::keywords().into_keys().collect();
+        names.extend(self.rox.globals().into_iter().map(|(name, _)| name));
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn prompt(&self) -> &'static str {
+        if self.pending.is_empty() {
+            "> "
+        } else {
+            "... "
+        }
+    }
+
+    /// Handles a line of input, returning false once the session should end
+    fn handle(&mut self, input: &str) -> bool {
+        if self.pending.is_empty() {
+            if let Some(command) = input.trim().strip_prefix(':') {
+                let (name, argument) = match command.find(char::is_whitespace) {
+                    Some(index) => (&command[..index], command[index..].trim()),
+                    None => (command, ""),
+                };
+                return self.command(name, argument);
+            }
+
+            if input.trim().is_empty() {
+                return true;
+            }
+
+            self.pending_offset = self.transcript.len();
+            self.pending_line = self.line;
+        }
+
+        self.transcript.push_str(input);
+        self.line += input.matches('\n').count();
+        self.pending.push_str(input);
+
+        // An empty line sends whatever has been typed so far, even if it's unfinished
+        let finished = input.trim().is_empty();
+        let source = std::mem::take(&mut self.pending);
+        match self
+            .rox
+            .eval_line(&source, self.pending_offset, self.pending_line)
+        {
+            Ok(Some(value)) => println!("{}", value),
+            Ok(None) => {}
+            Err(errors) if !finished && self.is_incomplete(&errors) => self.pending = source,
+
+            // An error only affects the input it was in, so the session carries on
+            Err(errors) => report(errors, &self.transcript, "<stdin>"),
+        }
+
+        true
+    }
+
+    /// Whether the errors all come from the input ending too soon, so that more lines might fix
+    /// them
+    fn is_incomplete(&self, errors: &[RoxError]) -> bool {
+        errors.iter().all(|error| {
+            let end = error.span.offset + error.span.length;
+            let at_end = self
+                .transcript
+                .get(end..)
+                .is_none_or(|rest| rest.trim().is_empty());
+            at_end && (error.kind == ErrorKind::Scan || error.kind == ErrorKind::Parse)
+        })
+    }
+
+    /// Runs a meta-command, returning false once the session should end
+    fn command(&mut self, name: &str, argument: &str) -> bool {
+        match name {
+            "env" => {
+                for (name, value) in self.rox.globals() {
+                    println!("{} = {}", name, value);
+                }
+            }
+            "ast" => match self.rox.parse_expression(argument) {
+                Ok(expr) => println!("{}", expr),
+                Err(errors) => report(errors, argument, "<input>"),
+            },
+            "tokens" => match Scanner::new(argument.to_string()).scan_tokens() {
+                Ok(tokens) => {
+                    for token in tokens {
+                        let span = token.span;
+                        println!(
+                            "{}:{} {:?} '{}'",
+                            span.line, span.column, token.type_, token.lexeme
+                        );
+                    }
+                }
+                Err(errors) => report(errors, argument, "<input>"),
+            },
+            "load" => match std::fs::read_to_string(argument) {
+                Ok(source) => {
+                    if let Err(errors) = self.rox.eval_str(&source) {
+                        report(errors, &source, argument);
+                    }
+                }
+                Err(error) => {
+                    let message = format!("Cannot read '{}': {}", argument, error);
+                    let error = RoxError::new(ErrorKind::Io, message, Default::default(), None);
+                    report(vec![error], "", argument);
+                }
+            },
+            "reset" => *self = Repl::new(),
+            "quit" => return false,
+            "help" => println!("{}", HELP),
+            _ => eprintln!("Unknown command ':{}', try :help", name),
+        }
+
+        true
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::home_dir().map(|home| home.join(".rox_history"))
+}
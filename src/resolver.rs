@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+
+use crate::error::ErrorKind;
+use crate::error::RoxError;
+use crate::parser::Depth;
+use crate::parser::Expr;
+use crate::parser::FunctionDeclaration;
+use crate::parser::Stmt;
+use crate::scanner::Span;
+use crate::scanner::Token;
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Initializer,
+    Method,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+/// A variable declared in a local scope
+struct Local {
+    /// Whether the variable's initializer has finished, so that we can catch variables being read
+    /// in their own initializer
+    defined: bool,
+    span: Span,
+}
+
+/// Walks the parsed statements before they are interpreted and records, on each variable
+/// reference, how many scopes out its declaration lives. Also catches errors that can be spotted
+/// without running the code
+pub struct Resolver {
+    /// One entry per local scope
+    scopes: Vec<HashMap<String, Local>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    errors: Vec<RoxError>,
+}
+
+impl Default for Resolver {
+    fn default() -> Resolver {
+        Resolver::new()
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Resolver {
+        Resolver {
+            scopes: Vec::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn resolve(&mut self, statements: &[Stmt]) -> Result<(), Vec<RoxError>> {
+        self.resolve_statements(statements);
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    fn resolve_statements(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            self.resolve_statement(statement);
+        }
+    }
+
+    fn resolve_statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Block(statements, _) => {
+                self.begin_scope();
+                self.resolve_statements(statements);
+                self.end_scope();
+            }
+            Stmt::Class(name, superclass, methods, _) => {
+                let enclosing_class = self.current_class;
+                self.current_class = ClassType::Class;
+
+                self.declare(name);
+                self.define(name);
+
+                if let Some(superclass) = superclass {
+                    if let Expr::Variable(superclass_name, _) = superclass {
+                        if superclass_name.lexeme == name.lexeme {
+                            self.error(superclass_name, "A class cannot inherit from itself");
+                        }
+                    }
+
+                    self.current_class = ClassType::Subclass;
+                    self.resolve_expression(superclass);
+
+                    self.begin_scope();
+                    self.define_keyword("super");
+                }
+
+                self.begin_scope();
+                self.define_keyword("this");
+
+                for method in methods {
+                    let type_ = if method.name.lexeme == "init" {
+                        FunctionType::Initializer
+                    } else {
+                        FunctionType::Method
+                    };
+                    self.resolve_function(method, type_);
+                }
+
+                self.end_scope();
+
+                if superclass.is_some() {
+                    self.end_scope();
+                }
+
+                self.current_class = enclosing_class;
+            }
+            Stmt::Expression(expr, _) => self.resolve_expression(expr),
+            Stmt::Function(declaration) => {
+                self.declare(&declaration.name);
+                self.define(&declaration.name);
+                self.resolve_function(declaration, FunctionType::Function);
+            }
+            Stmt::If(condition, then_branch, else_branch, _) => {
+                self.resolve_expression(condition);
+                self.resolve_statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.resolve_statement(else_branch);
+                }
+            }
+            Stmt::Print(expr, _) => self.resolve_expression(expr),
+            Stmt::Return(keyword, value, _) => {
+                if self.current_function == FunctionType::None {
+                    self.error(keyword, "Cannot return from top-level code");
+                }
+                if let Some(value) = value {
+                    if self.current_function == FunctionType::Initializer {
+                        self.error(keyword, "Cannot return a value from an initializer");
+                    }
+                    self.resolve_expression(value);
+                }
+            }
+            Stmt::Var(name, initializer, _) => {
+                self.declare(name);
+                if let Some(initializer) = initializer {
+                    self.resolve_expression(initializer);
+                }
+                self.define(name);
+            }
+            Stmt::While(condition, body, _) => {
+                self.resolve_expression(condition);
+                self.resolve_statement(body);
+            }
+        }
+    }
+
+    fn resolve_expression(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Assign(name, value, depth) => {
+                self.resolve_expression(value);
+                self.resolve_local(name, depth);
+            }
+            Expr::Binary(left, _operator, right) => {
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            }
+            Expr::Call(callee, _paren, arguments) => {
+                self.resolve_expression(callee);
+                for argument in arguments {
+                    self.resolve_expression(argument);
+                }
+            }
+            Expr::Get(object, _name) => self.resolve_expression(object),
+            Expr::Grouping(inner_expr, _) => self.resolve_expression(inner_expr),
+            Expr::Interpolation(parts, _) => {
+                for part in parts {
+                    self.resolve_expression(part);
+                }
+            }
+            Expr::Literal(..) => {}
+            Expr::Logical(left, _operator, right) => {
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            }
+            Expr::Set(object, _name, value) => {
+                self.resolve_expression(value);
+                self.resolve_expression(object);
+            }
+            Expr::Super(keyword, _method, depth) => {
+                match self.current_class {
+                    ClassType::None => self.error(keyword, "Cannot use 'super' outside of a class"),
+                    ClassType::Class => self.errors.push(
+                        RoxError::at_token(
+                            ErrorKind::Resolve,
+                            keyword,
+                            "Cannot use 'super' in a class with no superclass",
+                        )
+                        .with_help("add a superclass with 'class Name < Superclass'"),
+                    ),
+                    ClassType::Subclass => {}
+                }
+                self.resolve_local(keyword, depth);
+            }
+            Expr::This(keyword, depth) => {
+                if self.current_class == ClassType::None {
+                    self.error(keyword, "Cannot use 'this' outside of a class");
+                }
+                self.resolve_local(keyword, depth);
+            }
+            Expr::Unary(_operator, inner_expr) => self.resolve_expression(inner_expr),
+            Expr::Variable(name, depth) => {
+                let declared_at = self
+                    .scopes
+                    .last()
+                    .and_then(|scope| scope.get(&name.lexeme))
+                    .filter(|local| !local.defined)
+                    .map(|local| local.span);
+
+                if let Some(span) = declared_at {
+                    self.errors.push(
+                        RoxError::at_token(
+                            ErrorKind::Resolve,
+                            name,
+                            "Cannot read local variable in its own initializer",
+                        )
+                        .with_note("variable declared here", Some(span)),
+                    );
+                }
+
+                self.resolve_local(name, depth);
+            }
+        }
+    }
+
+    fn resolve_function(&mut self, declaration: &FunctionDeclaration, type_: FunctionType) {
+        let enclosing_function = self.current_function;
+        self.current_function = type_;
+
+        self.begin_scope();
+        for param in &declaration.params {
+            self.declare(param);
+            self.define(param);
+        }
+        self.resolve_statements(&declaration.body);
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let local = Local {
+            defined: false,
+            span: name.span,
+        };
+        let previous = match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name.lexeme.clone(), local),
+            None => None,
+        };
+
+        if let Some(previous) = previous {
+            self.errors.push(
+                RoxError::at_token(
+                    ErrorKind::Resolve,
+                    name,
+                    "Variable with this name already declared in this scope",
+                )
+                .with_note("previously declared here", Some(previous.span)),
+            );
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            let local = Local {
+                defined: true,
+                span: name.span,
+            };
+            scope.insert(name.lexeme.clone(), local);
+        }
+    }
+
+    /// Defines the implicit variables 'this' and 'super', which have no declaration in the source
+    fn define_keyword(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            let local = Local {
+                defined: true,
+                span: Span::default(),
+            };
+            scope.insert(name.to_string(), local);
+        }
+    }
+
+    /// Records the number of scopes between the reference and the declaration. Anything not
+    /// found in a local scope is left unresolved and assumed to be global
+    fn resolve_local(&mut self, name: &Token, depth: &Depth) {
+        for (hops, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&name.lexeme) {
+                depth.set(Some(hops));
+                return;
+            }
+        }
+    }
+
+    fn error(&mut self, token: &Token, message: &str) {
+        self.errors
+            .push(RoxError::at_token(ErrorKind::Resolve, token, message));
+    }
+}
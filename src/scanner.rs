@@ -1,25 +1,70 @@
 use std::collections::HashMap;
 
-use crate::error;
+use unicode_xid::UnicodeXID;
+
+use crate::error::ErrorKind;
+use crate::error::RoxError;
 use crate::value::Value;
 
-pub fn line_error(line: usize, message: &str) {
-    error::report(line, "", message);
+/// The start of an expression interpolated into a string
+struct Interpolation {
+    span: Span,
+    /// The braces opened within the expression, so that we know which '}' ends it
+    braces: usize,
 }
 
 pub struct Scanner {
     source: String,
     tokens: Vec<Token>,
+    errors: Vec<RoxError>,
 
+    // Byte offsets into the source. These always fall on character boundaries
     start: usize,
     current: usize,
+
+    // The position of `current`. Columns count characters rather than bytes
     line: usize,
+    column: usize,
+
+    // Where the token currently being scanned began
+    start_line: usize,
+    start_column: usize,
+
+    /// Added to the byte offset of every span, for source that follows on from earlier input
+    base_offset: usize,
+
+    /// The `${` of each interpolated expression that is still being scanned, innermost last
+    interpolations: Vec<Interpolation>,
 
     keywords: HashMap<String, TokenType>,
 }
 
 impl Scanner {
     pub fn new(source: String) -> Scanner {
+        Scanner::starting_at(source, 0, 1)
+    }
+
+    /// A scanner for source that follows on from earlier input, such as a line typed into the
+    /// REPL, so that spans point into the input as a whole
+    pub fn starting_at(source: String, offset: usize, line: usize) -> Scanner {
+        Scanner {
+            source,
+            tokens: Vec::new(),
+            errors: Vec::new(),
+            start: 0,
+            current: 0,
+            line,
+            column: 1,
+            start_line: line,
+            start_column: 1,
+            base_offset: offset,
+            interpolations: Vec::new(),
+            keywords: Scanner::keywords(),
+        }
+    }
+
+    /// The reserved words and the tokens they scan as
+    pub fn keywords() -> HashMap<String, TokenType> {
         let mut keywords = HashMap::new();
         keywords.insert("and".to_string(), TokenType::And);
         keywords.insert("class".to_string(), TokenType::Class);
@@ -38,26 +83,45 @@ impl Scanner {
         keywords.insert("var".to_string(), TokenType::Var);
         keywords.insert("while".to_string(), TokenType::While);
 
-        Scanner {
-            source,
-            tokens: Vec::new(),
-            start: 0,
-            current: 0,
-            line: 0,
-            keywords,
-        }
+        keywords
     }
 
-    pub fn scan_tokens(&mut self) -> Vec<Token> {
+    /// Scans the whole source, carrying on past errors so that they can all be reported at once
+    pub fn scan_tokens(&mut self) -> Result<Vec<Token>, Vec<RoxError>> {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column;
             self.scan_token();
         }
 
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column;
+
+        for interpolation in std::mem::take(&mut self.interpolations) {
+            let lexeme = Some("${".to_string());
+            self.error_at(
+                interpolation.span,
+                "Unterminated string interpolation",
+                lexeme,
+            );
+        }
+
+        // Errors at the end of the input point just past the last token, rather than at whatever
+        // whitespace comes after it
+        let span = match self.tokens.last() {
+            Some(last) => last.span_after(),
+            None => self.span(),
+        };
         self.tokens
-            .push(Token::new(TokenType::Eof, String::new(), None, self.line));
+            .push(Token::new(TokenType::Eof, String::new(), None, span));
 
-        self.tokens.clone()
+        if self.errors.is_empty() {
+            Ok(std::mem::take(&mut self.tokens))
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
     }
 
     pub fn scan_token(&mut self) {
@@ -65,14 +129,36 @@ impl Scanner {
         match c {
             '(' => self.add_token(TokenType::LeftParen),
             ')' => self.add_token(TokenType::RightParen),
-            '{' => self.add_token(TokenType::LeftBrace),
-            '}' => self.add_token(TokenType::RightBrace),
+            '{' => {
+                if let Some(interpolation) = self.interpolations.last_mut() {
+                    interpolation.braces += 1;
+                }
+                self.add_token(TokenType::LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                // The end of an interpolated expression, so carry on with the rest of the string
+                Some(interpolation) if interpolation.braces == 0 => {
+                    self.interpolations.pop();
+                    self.string(false, true)
+                }
+                Some(interpolation) => {
+                    interpolation.braces -= 1;
+                    self.add_token(TokenType::RightBrace)
+                }
+                None => self.add_token(TokenType::RightBrace),
+            },
             ',' => self.add_token(TokenType::Comma),
             '.' => self.add_token(TokenType::Dot),
             '-' => self.add_token(TokenType::Minus),
             '+' => self.add_token(TokenType::Plus),
             ';' => self.add_token(TokenType::Semicolon),
             '*' => self.add_token(TokenType::Star),
+            '%' => self.add_token(TokenType::Percent),
+            // '//' already starts a comment, so floor division is spelt '~/'
+            '~' if self.peek() == '/' => {
+                self.advance();
+                self.add_token(TokenType::TildeSlash)
+            }
             '!' => {
                 let token = if self.match_('=') {
                     TokenType::BangEqual
@@ -111,15 +197,22 @@ impl Scanner {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                } else if self.match_('*') {
+                    self.block_comment()
                 } else {
                     self.add_token(TokenType::Slash)
                 }
             }
-            '"' => self.string(),
+            '"' => self.string(false, false),
             ' ' => {}
             '\r' => {}
             '\t' => {}
-            '\n' => self.line += 1,
+            '\n' => {}
+
+            'r' if self.peek() == '"' => {
+                self.advance();
+                self.string(true, false)
+            }
 
             _ => {
                 if Scanner::is_digit(c) {
@@ -127,47 +220,71 @@ impl Scanner {
                 } else if Scanner::is_alpha(c) {
                     self.identifier()
                 } else {
-                    line_error(self.line, "Unexpected character")
+                    let lexeme = self.source[self.start..self.current].to_string();
+                    self.error("Unexpected character", Some(lexeme))
                 }
             }
         }
     }
 
+    fn error(&mut self, message: &str, lexeme: Option<String>) {
+        self.error_at(self.span(), message, lexeme);
+    }
+
+    fn error_at(&mut self, span: Span, message: &str, lexeme: Option<String>) {
+        self.errors.push(RoxError::new(
+            ErrorKind::Scan,
+            message.to_string(),
+            span,
+            lexeme,
+        ));
+    }
+
+    /// The span from the start of the current token up to the current position
+    fn span(&self) -> Span {
+        Span::new(
+            self.base_offset + self.start,
+            self.current - self.start,
+            self.start_line,
+            self.start_column,
+        )
+    }
+
     fn match_(&mut self, c: char) -> bool {
-        if self.is_at_end() {
-            return false;
-        }
-        if self.source.chars().nth(self.current).unwrap_or('\0') != c {
+        if self.peek() != c || self.is_at_end() {
             return false;
         }
 
-        self.current += 1;
+        self.advance();
         true
     }
 
-    fn peek(&mut self) -> char {
-        if self.is_at_end() {
-            '\0'
-        } else {
-            self.source.chars().nth(self.current).unwrap_or('\0')
-        }
+    fn peek(&self) -> char {
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
 
-    fn peek_next(&mut self) -> char {
-        if self.current + 1 >= self.source.len() {
-            '\0'
-        } else {
-            self.source.chars().nth(self.current + 1).unwrap_or('\0')
-        }
+    fn peek_next(&self) -> char {
+        let mut chars = self.source[self.current..].chars();
+        chars.next();
+        chars.next().unwrap_or('\0')
     }
 
+    /// Moves past the next character, keeping track of the line and column
     fn advance(&mut self) -> char {
-        self.current += 1;
-        self.source.chars().nth(self.current - 1).unwrap_or(' ')
+        let c = self.peek();
+        self.current += c.len_utf8();
+
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        c
     }
 
     fn is_at_end(&self) -> bool {
-        self.current == self.source.len()
+        self.current >= self.source.len()
     }
 
     fn add_token(&mut self, type_: TokenType) {
@@ -175,57 +292,248 @@ impl Scanner {
     }
 
     fn add_token_value(&mut self, type_: TokenType, value: Option<Value>) {
-        let len = self.current - self.start;
-        let text = self.source.chars().skip(self.start).take(len).collect();
-        self.tokens.push(Token::new(type_, text, value, self.line));
+        let text = self.source[self.start..self.current].to_string();
+        self.tokens
+            .push(Token::new(type_, text, value, self.span()));
     }
 
-    fn string(&mut self) {
+    /// Skips a /* ... */ comment whose opening has been consumed. Block comments can nest, so
+    /// the comment only ends once every /* inside it has been closed
+    fn block_comment(&mut self) {
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                // The span runs from the opening /* to the end of the source
+                self.error("Unterminated block comment", None);
+                return;
+            }
+
+            if self.peek() == '/' && self.peek_next() == '*' {
+                self.advance();
+                self.advance();
+                depth += 1;
+            } else if self.peek() == '*' && self.peek_next() == '/' {
+                self.advance();
+                self.advance();
+                depth -= 1;
+            } else {
+                self.advance();
+            }
+        }
+    }
+
+    /// Scans a string literal whose opening quote has been consumed. Raw strings keep any
+    /// backslashes and `${` as they are.
+    ///
+    /// A string containing `${expr}` is split up around its expressions. The text up to the
+    /// first `${` is an InterpolationStart token, and the expression's tokens follow it. Once the
+    /// closing '}' is reached we come back here to continue the string, which is then either an
+    /// InterpolationMiddle before another `${` or the InterpolationEnd
+    fn string(&mut self, raw: bool, continued: bool) {
+        let mut value = String::new();
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1
+            if !raw && self.peek() == '$' && self.peek_next() == '{' {
+                let span = Span::new(self.base_offset + self.current, 2, self.line, self.column);
+                self.advance();
+                self.advance();
+                self.interpolations.push(Interpolation { span, braces: 0 });
+
+                let type_ = if continued {
+                    TokenType::InterpolationMiddle
+                } else {
+                    TokenType::InterpolationStart
+                };
+                self.add_token_value(type_, Some(Value::String(value)));
+                return;
+            }
+
+            let escape = (self.current, self.line, self.column);
+            let c = self.advance();
+            if raw || c != '\\' {
+                value.push(c);
+                continue;
+            }
+
+            match self.escape() {
+                Ok(Some(c)) => value.push(c),
+                Ok(None) => {}
+                Err(message) => {
+                    let (offset, line, column) = escape;
+                    let span = Span::new(
+                        self.base_offset + offset,
+                        self.current - offset,
+                        line,
+                        column,
+                    );
+                    let lexeme = self.source[offset..self.current].to_string();
+                    self.error_at(span, &message, Some(lexeme));
+                }
             }
-            self.advance();
         }
 
         // Check for unterminated string
         if self.is_at_end() {
-            line_error(self.line, "Unterminated string.");
+            self.error("Unterminated string", None);
             return;
         }
 
         // The closing "
         self.advance();
 
-        let len = self.current - self.start;
-        let value = self
-            .source
-            .chars()
-            .skip(self.start + 1)
-            .take(len - 2)
-            .collect();
-        self.add_token_value(TokenType::String, Some(Value::String(value)));
+        let type_ = if continued {
+            TokenType::InterpolationEnd
+        } else {
+            TokenType::String
+        };
+        self.add_token_value(type_, Some(Value::String(value)));
     }
 
-    fn number(&mut self) {
-        while Scanner::is_digit(self.peek()) {
+    /// Decodes the escape sequence following a backslash. Nothing is produced when the source
+    /// ends straight after the backslash, which is reported as an unterminated string
+    fn escape(&mut self) -> Result<Option<char>, String> {
+        if self.is_at_end() {
+            return Ok(None);
+        }
+
+        match self.advance() {
+            'n' => Ok(Some('\n')),
+            't' => Ok(Some('\t')),
+            '\\' => Ok(Some('\\')),
+            '"' => Ok(Some('"')),
+            '$' => Ok(Some('$')),
+            'u' => self.unicode_escape().map(Some),
+            _ => Err("Invalid escape sequence".to_string()),
+        }
+    }
+
+    /// Decodes the `{...}` part of a `\u{...}` escape, which holds 1 to 6 hex digits
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        if !self.match_('{') {
+            return Err("Expect '{' after '\\u'".to_string());
+        }
+
+        let digits_start = self.current;
+        while self.peek().is_ascii_hexdigit() {
             self.advance();
         }
+        let digits_end = self.current;
+
+        if !self.match_('}') {
+            return Err("Expect '}' after unicode escape digits".to_string());
+        }
+
+        let digits = &self.source[digits_start..digits_end];
+        if digits.is_empty() || digits.len() > 6 {
+            return Err("Unicode escape must have 1 to 6 hex digits".to_string());
+        }
 
-        if self.peek() == '.' && Scanner::is_digit(self.peek_next()) {
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("'{}' is not a valid unicode scalar value", digits))
+    }
+
+    /// Scans a number literal, which may have a 0x, 0b or 0o prefix, a fraction, an exponent
+    /// and '_' separators between its digits
+    fn number(&mut self) {
+        let prefixed = self.source[self.start..].starts_with('0')
+            && matches!(self.peek(), 'x' | 'X' | 'b' | 'B' | 'o' | 'O');
+
+        if prefixed {
             self.advance();
+        } else {
+            while Scanner::is_digit(self.peek()) || self.peek() == '_' {
+                self.advance();
+            }
 
-            while Scanner::is_digit(self.peek()) {
+            if self.peek() == '.' && Scanner::is_digit(self.peek_next()) {
                 self.advance();
+
+                while Scanner::is_digit(self.peek()) || self.peek() == '_' {
+                    self.advance();
+                }
+            }
+
+            if matches!(self.peek(), 'e' | 'E') {
+                self.advance();
+                if matches!(self.peek(), '+' | '-') {
+                    self.advance();
+                }
+
+                while Scanner::is_digit(self.peek()) || self.peek() == '_' {
+                    self.advance();
+                }
             }
         }
 
-        let len = self.current - self.start;
-        let text: String = self.source.chars().skip(self.start).take(len).collect();
-        self.add_token_value(
-            TokenType::Number,
-            Some(Value::Double(text.parse::<f64>().unwrap())),
-        )
+        // Take anything else stuck to the literal, like the 'z' in '12z', so that it is reported
+        // as part of a malformed number rather than as a separate identifier
+        while Scanner::is_alpha_numeric(self.peek()) {
+            self.advance();
+        }
+
+        let text = &self.source[self.start..self.current];
+        match Scanner::number_value(text) {
+            Ok(value) => self.add_token_value(TokenType::Number, Some(value)),
+            Err(message) => {
+                let lexeme = Some(text.to_string());
+                self.error(&message, lexeme)
+            }
+        }
+    }
+
+    /// Works out the value of a number literal, or what is wrong with it. Literals without a
+    /// fraction or an exponent are integers
+    fn number_value(text: &str) -> Result<Value, String> {
+        let (radix, name, digits) = match text.get(..2) {
+            Some("0x") | Some("0X") => (16, "hexadecimal", &text[2..]),
+            Some("0b") | Some("0B") => (2, "binary", &text[2..]),
+            Some("0o") | Some("0O") => (8, "octal", &text[2..]),
+            _ => (10, "decimal", text),
+        };
+
+        let chars: Vec<char> = digits.chars().collect();
+        for (i, c) in chars.iter().enumerate() {
+            let is_digit = |c: Option<&char>| c.is_some_and(|c| c.is_digit(radix));
+            if *c == '_' && !(i > 0 && is_digit(chars.get(i - 1)) && is_digit(chars.get(i + 1))) {
+                return Err("Digit separator '_' must be between digits".to_string());
+            }
+        }
+
+        let cleaned: String = chars.iter().filter(|c| **c != '_').collect();
+
+        if radix != 10 {
+            if cleaned.is_empty() {
+                return Err(format!("Expect digits after '{}'", &text[..2]));
+            }
+            if let Some(c) = cleaned.chars().find(|c| !c.is_digit(radix)) {
+                return Err(format!("Invalid digit '{}' in {} literal", c, name));
+            }
+
+            return i64::from_str_radix(&cleaned, radix)
+                .map(Value::Int)
+                .map_err(|_| "Integer literal is too large".to_string());
+        }
+
+        if let Some(c) = cleaned
+            .chars()
+            .find(|c| !(c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-')))
+        {
+            return Err(format!("Invalid character '{}' in number literal", c));
+        }
+
+        if cleaned.chars().all(|c| c.is_ascii_digit()) {
+            return cleaned
+                .parse::<i64>()
+                .map(Value::Int)
+                .map_err(|_| "Integer literal is too large".to_string());
+        }
+
+        match cleaned.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(Value::Double(value)),
+            Ok(_) => Err("Number literal is too large".to_string()),
+            Err(_) => Err("Expect digits in exponent".to_string()),
+        }
     }
 
     fn identifier(&mut self) {
@@ -233,10 +541,9 @@ impl Scanner {
             self.advance();
         }
 
-        let len = self.current - self.start;
-        let text: String = self.source.chars().skip(self.start).take(len).collect();
+        let text = &self.source[self.start..self.current];
 
-        let token = match self.keywords.get(&text) {
+        let token = match self.keywords.get(text) {
             Some(token) => token.clone(),
             None => TokenType::Identifier,
         };
@@ -247,15 +554,16 @@ impl Scanner {
     // Stand alone
 
     fn is_digit(c: char) -> bool {
-        c >= '0' && c <= '9'
+        c.is_ascii_digit()
     }
 
+    /// Identifiers follow Unicode's XID rules, with '_' also allowed at the start
     fn is_alpha(c: char) -> bool {
-        (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || c == '_'
+        c.is_xid_start() || c == '_'
     }
 
     fn is_alpha_numeric(c: char) -> bool {
-        Scanner::is_alpha(c) || Scanner::is_digit(c)
+        c.is_xid_continue()
     }
 }
 
@@ -273,6 +581,7 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Percent,
 
     // one or two character tokens.
     Bang,
@@ -283,10 +592,15 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    TildeSlash,
 
     // literals.
     Identifier,
     String,
+    // Parts of a string with expressions interpolated into it
+    InterpolationStart,
+    InterpolationMiddle,
+    InterpolationEnd,
     Number,
 
     // // keywords.
@@ -309,57 +623,95 @@ pub enum TokenType {
     Eof,
 }
 
-impl std::string::ToString for TokenType {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for TokenType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let string = match self {
             // Single-character tokens.
-            TokenType::LeftParen => "(".to_string(),
-            TokenType::RightParen => ")".to_string(),
-            TokenType::LeftBrace => "{".to_string(),
-            TokenType::RightBrace => "}".to_string(),
-            TokenType::Comma => ",".to_string(),
-            TokenType::Dot => ".".to_string(),
-            TokenType::Minus => "-".to_string(),
-            TokenType::Plus => "+".to_string(),
-            TokenType::Semicolon => ";".to_string(),
-            TokenType::Slash => "/".to_string(),
-            TokenType::Star => "*".to_string(),
+            TokenType::LeftParen => "(",
+            TokenType::RightParen => ")",
+            TokenType::LeftBrace => "{",
+            TokenType::RightBrace => "}",
+            TokenType::Comma => ",",
+            TokenType::Dot => ".",
+            TokenType::Minus => "-",
+            TokenType::Plus => "+",
+            TokenType::Semicolon => ";",
+            TokenType::Slash => "/",
+            TokenType::Star => "*",
+            TokenType::Percent => "%",
 
             // one or two character tokens.
-            TokenType::Bang => "!".to_string(),
-            TokenType::BangEqual => "!=".to_string(),
-            TokenType::Equal => "=".to_string(),
-            TokenType::EqualEqual => "==".to_string(),
-            TokenType::Greater => ">".to_string(),
-            TokenType::GreaterEqual => ">=".to_string(),
-            TokenType::Less => "<".to_string(),
-            TokenType::LessEqual => "<=".to_string(),
+            TokenType::Bang => "!",
+            TokenType::BangEqual => "!=",
+            TokenType::Equal => "=",
+            TokenType::EqualEqual => "==",
+            TokenType::Greater => ">",
+            TokenType::GreaterEqual => ">=",
+            TokenType::Less => "<",
+            TokenType::LessEqual => "<=",
+            TokenType::TildeSlash => "~/",
 
             // literals.
-            TokenType::Identifier => "".to_string(),
-            TokenType::String => "".to_string(),
-            TokenType::Number => "".to_string(),
+            TokenType::Identifier => "",
+            TokenType::String => "",
+            TokenType::InterpolationStart => "",
+            TokenType::InterpolationMiddle => "",
+            TokenType::InterpolationEnd => "",
+            TokenType::Number => "",
 
             // keywords.
-            TokenType::And => "".to_string(),
-            TokenType::Class => "".to_string(),
-            TokenType::Else => "".to_string(),
-            TokenType::False => "".to_string(),
-            TokenType::Fun => "".to_string(),
-            TokenType::For => "".to_string(),
-            TokenType::If => "".to_string(),
-            TokenType::Nil => "".to_string(),
-            TokenType::Or => "".to_string(),
-            TokenType::Print => "".to_string(),
-            TokenType::Return => "".to_string(),
-            TokenType::Super => "".to_string(),
-            TokenType::This => "".to_string(),
-            TokenType::True => "".to_string(),
-            TokenType::Var => "".to_string(),
-            TokenType::While => "".to_string(),
-            TokenType::Eof => "".to_string(),
+            TokenType::And => "",
+            TokenType::Class => "",
+            TokenType::Else => "",
+            TokenType::False => "",
+            TokenType::Fun => "",
+            TokenType::For => "",
+            TokenType::If => "",
+            TokenType::Nil => "",
+            TokenType::Or => "",
+            TokenType::Print => "",
+            TokenType::Return => "",
+            TokenType::Super => "",
+            TokenType::This => "",
+            TokenType::True => "",
+            TokenType::Var => "",
+            TokenType::While => "",
+            TokenType::Eof => "",
+        };
+        write!(f, "{}", string)
+    }
+}
+
+/// A region of the source. Lines and columns count from 1 and columns count characters rather
+/// than bytes
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub offset: usize,
+    pub length: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(offset: usize, length: usize, line: usize, column: usize) -> Span {
+        Span {
+            offset,
+            length,
+            line,
+            column,
         }
     }
+
+    /// The span covering this span through to the end of the other one
+    pub fn to(&self, end: Span) -> Span {
+        let end_offset = std::cmp::max(self.offset + self.length, end.offset + end.length);
+        Span::new(
+            self.offset,
+            end_offset - self.offset,
+            self.line,
+            self.column,
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -367,18 +719,33 @@ pub struct Token {
     pub type_: TokenType,
     pub lexeme: String,
     pub literal: Option<Value>,
-    pub line: usize,
+    pub span: Span,
 }
 
 pub type TokenVec = Vec<Token>;
 
 impl Token {
-    pub fn new(type_: TokenType, lexeme: String, literal: Option<Value>, line: usize) -> Token {
+    pub fn new(type_: TokenType, lexeme: String, literal: Option<Value>, span: Span) -> Token {
         Token {
             type_,
             lexeme,
             literal,
-            line,
+            span,
         }
     }
+
+    /// An empty span just past the end of the token
+    fn span_after(&self) -> Span {
+        let (line, column) = match self.lexeme.rfind('\n') {
+            Some(index) => (
+                self.span.line + self.lexeme.matches('\n').count(),
+                self.lexeme[index + 1..].chars().count() + 1,
+            ),
+            None => (
+                self.span.line,
+                self.span.column + self.lexeme.chars().count(),
+            ),
+        };
+        Span::new(self.span.offset + self.span.length, 0, line, column)
+    }
 }
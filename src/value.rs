@@ -1,18 +1,41 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::class::Class;
+use crate::class::Instance;
+use crate::function::Function;
+use crate::function::NativeFunction;
+
+/// Functions, classes and instances are compared by identity, so each is only equal to itself
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     String(String),
+    Int(i64),
     Double(f64),
     Bool(bool),
+    Function(Rc<Function>),
+    NativeFunction(Rc<NativeFunction>),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
     Nil,
 }
 
-impl std::string::ToString for Value {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Value::String(string) => format!("\"{}\"", string.clone()),
-            Value::Double(double) => double.to_string(),
-            Value::Bool(boolean) => boolean.to_string(),
-            Value::Nil => "nil".to_string(),
+            Value::String(string) => write!(f, "\"{}\"", string),
+            Value::Int(int) => write!(f, "{}", int),
+            // Whole doubles keep their '.0' so that they can be told apart from ints
+            Value::Double(double) if double.is_finite() && double.fract() == 0.0 => {
+                write!(f, "{:.1}", double)
+            }
+            Value::Double(double) => write!(f, "{}", double),
+            Value::Bool(boolean) => write!(f, "{}", boolean),
+            Value::Function(function) => write!(f, "<fn {}>", function.name()),
+            Value::NativeFunction(function) => write!(f, "<native fn {}>", function.name),
+            Value::Class(class) => write!(f, "{}", class.name),
+            Value::Instance(instance) => write!(f, "{} instance", instance.borrow().class.name),
+            Value::Nil => write!(f, "nil"),
         }
     }
 }
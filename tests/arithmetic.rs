@@ -0,0 +1,99 @@
+mod common;
+
+use common::eval;
+use rox::Value;
+
+fn int(value: i64) -> Result<Option<Value>, String> {
+    Ok(Some(Value::Int(value)))
+}
+
+fn double(value: f64) -> Result<Option<Value>, String> {
+    Ok(Some(Value::Double(value)))
+}
+
+#[test]
+fn floor_division_rounds_towards_negative_infinity() {
+    assert_eq!(eval("7 ~/ 2"), int(3));
+    assert_eq!(eval("-7 ~/ 2"), int(-4));
+    assert_eq!(eval("7 ~/ -2"), int(-4));
+    assert_eq!(eval("-7 ~/ -2"), int(3));
+    assert_eq!(eval("6 ~/ -2"), int(-3));
+    assert_eq!(eval("-7.5 ~/ 2"), double(-4.0));
+}
+
+#[test]
+fn modulo_takes_the_sign_of_the_divisor() {
+    assert_eq!(eval("7 % 3"), int(1));
+    assert_eq!(eval("-7 % 3"), int(2));
+    assert_eq!(eval("7 % -3"), int(-2));
+    assert_eq!(eval("-7 % -3"), int(-1));
+    assert_eq!(eval("6 % -3"), int(0));
+    assert_eq!(eval("-7.5 % 2"), double(0.5));
+}
+
+#[test]
+fn ints_are_promoted_when_mixed_with_doubles() {
+    assert_eq!(eval("1 + 2"), int(3));
+    assert_eq!(eval("1 + 2.5"), double(3.5));
+    assert_eq!(eval("2.5 * 2"), double(5.0));
+    assert_eq!(eval("7 / 2"), double(3.5));
+    assert_eq!(eval("1 == 1.0"), Ok(Some(Value::Bool(true))));
+    assert_eq!(eval("1 < 1.5"), Ok(Some(Value::Bool(true))));
+}
+
+#[test]
+fn overflow_is_a_runtime_error() {
+    let overflow = Err("Integer overflow".to_string());
+    assert_eq!(eval("9223372036854775807 + 1"), overflow);
+    assert_eq!(eval("-9223372036854775807 - 2"), overflow);
+    assert_eq!(eval("4611686018427387904 * 2"), overflow);
+    assert_eq!(eval("-(-9223372036854775807 - 1)"), overflow);
+    assert_eq!(eval("(-9223372036854775807 - 1) ~/ -1"), overflow);
+    assert_eq!(eval("(-9223372036854775807 - 1) % -1"), int(0));
+}
+
+#[test]
+fn integer_division_by_zero_is_a_runtime_error() {
+    let zero = Err("Division by zero".to_string());
+    assert_eq!(eval("1 ~/ 0"), zero);
+    assert_eq!(eval("1 % 0"), zero);
+}
+
+#[test]
+fn integer_literals_too_large_for_an_int_are_scan_errors() {
+    let too_large = Err("Integer literal is too large".to_string());
+    assert_eq!(eval("9223372036854775807"), int(i64::MAX));
+    assert_eq!(eval("9223372036854775808"), too_large);
+    assert_eq!(eval("0xFFFFFFFFFFFFFFFFF"), too_large);
+    assert_eq!(eval("99999999999999999999.0"), double(1e20));
+}
+
+#[test]
+fn ints_and_doubles_are_compared_exactly() {
+    let bool = |value| Ok(Some(Value::Bool(value)));
+    assert_eq!(
+        eval("9223372036854775807 == 9223372036854775806.0"),
+        bool(false)
+    );
+    assert_eq!(
+        eval("9223372036854775807 < 9223372036854775808.0"),
+        bool(true)
+    );
+    assert_eq!(eval("9007199254740993 > 9007199254740992.0"), bool(true));
+    assert_eq!(
+        eval("-9223372036854775807 - 1 == -9223372036854775808.0"),
+        bool(true)
+    );
+    assert_eq!(eval("1 == 1.0"), bool(true));
+    assert_eq!(eval("1 < 1.5"), bool(true));
+    assert_eq!(eval("-1 > -1.5"), bool(true));
+    assert_eq!(eval("0 == 0.0 / 0.0"), bool(false));
+}
+
+#[test]
+fn whole_doubles_are_displayed_with_a_fraction() {
+    assert_eq!(Value::Double(2.0).to_string(), "2.0");
+    assert_eq!(Value::Double(-0.0).to_string(), "-0.0");
+    assert_eq!(Value::Double(2.5).to_string(), "2.5");
+    assert_eq!(Value::Int(2).to_string(), "2");
+}
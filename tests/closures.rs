@@ -0,0 +1,88 @@
+mod common;
+
+use common::run;
+use common::string;
+use rox::Value;
+
+#[test]
+fn counter_keeps_counting_after_make_counter_returns() {
+    let rox = run(r#"
+        fun makeCounter() {
+            var i = 0;
+            fun count() {
+                i = i + 1;
+                return i;
+            }
+            return count;
+        }
+
+        var counter = makeCounter();
+        counter();
+        counter();
+        var result = counter();
+    "#);
+
+    assert_eq!(rox.get_global("result"), Some(Value::Int(3)));
+}
+
+#[test]
+fn each_counter_has_its_own_count() {
+    let rox = run(r#"
+        fun makeCounter() {
+            var i = 0;
+            fun count() {
+                i = i + 1;
+                return i;
+            }
+            return count;
+        }
+
+        var first = makeCounter();
+        var second = makeCounter();
+        first();
+        first();
+        var a = first();
+        var b = second();
+    "#);
+
+    assert_eq!(rox.get_global("a"), Some(Value::Int(3)));
+    assert_eq!(rox.get_global("b"), Some(Value::Int(1)));
+}
+
+#[test]
+fn closure_outlives_the_block_it_was_declared_in() {
+    let rox = run(r#"
+        var get;
+        {
+            var local = "inside";
+            fun f() {
+                return local;
+            }
+            get = f;
+        }
+        var result = get();
+    "#);
+
+    assert_eq!(rox.get_global("result"), string("inside"));
+}
+
+#[test]
+fn closure_keeps_the_variable_it_was_declared_with_when_a_block_shadows_it() {
+    let rox = run(r#"
+        var a = "global";
+        var first;
+        var second;
+        {
+            fun showA() {
+                return a;
+            }
+
+            first = showA();
+            var a = "block";
+            second = showA();
+        }
+    "#);
+
+    assert_eq!(rox.get_global("first"), string("global"));
+    assert_eq!(rox.get_global("second"), string("global"));
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::errors;
+use common::global;
+use rox::ErrorKind;
+use rox::Value;
+
+#[test]
+fn block_comments_are_skipped() {
+    assert_eq!(
+        global("var a = /* one */ 1 /* two */ + 2;", "a"),
+        Ok(Some(Value::Int(3)))
+    );
+}
+
+#[test]
+fn block_comments_nest() {
+    assert_eq!(
+        global("/* outer /* inner */ still a comment */ var a = 1;", "a"),
+        Ok(Some(Value::Int(1)))
+    );
+    assert_eq!(
+        global("/* /* /* */ */ var a = 2; */ var a = 3;", "a"),
+        Ok(Some(Value::Int(3)))
+    );
+}
+
+#[test]
+fn lines_are_counted_inside_block_comments() {
+    let errors = errors("/* one\ntwo\nthree */ var a = 1;\nprint -\"x\";");
+
+    assert_eq!(errors[0].kind, ErrorKind::Runtime);
+    assert_eq!(errors[0].span.line, 4);
+    assert_eq!(errors[0].span.column, 7);
+}
+
+#[test]
+fn unterminated_block_comment_is_reported_at_its_opening() {
+    let errors = errors("var a = 1;\n/* one\n/* two */\nthree");
+
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.kind, ErrorKind::Scan);
+    assert_eq!(error.message, "Unterminated block comment");
+    assert_eq!(error.span.line, 2);
+    assert_eq!(error.span.column, 1);
+    assert_eq!(error.span.offset, 11);
+}
+
+#[test]
+fn line_comment_markers_inside_a_block_comment_are_ignored() {
+    assert_eq!(
+        global("/* // not a line comment */ var a = 1;", "a"),
+        Ok(Some(Value::Int(1)))
+    );
+}
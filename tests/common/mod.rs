@@ -0,0 +1,44 @@
+//! Helpers shared by the integration tests. Each test crate only uses some of them
+#![allow(dead_code)]
+
+use rox::Rox;
+use rox::RoxError;
+use rox::Value;
+
+/// Runs a script, panicking if it has any errors
+pub fn run(source: &str) -> Rox {
+    let mut rox = Rox::new();
+    rox.eval_str(source).unwrap();
+    rox
+}
+
+/// The errors from running a script, panicking if it runs without any
+pub fn errors(source: &str) -> Vec<RoxError> {
+    Rox::new().eval_str(source).unwrap_err()
+}
+
+/// The messages of the errors from running a script
+pub fn messages(source: &str) -> Vec<String> {
+    errors(source)
+        .into_iter()
+        .map(|error| error.message)
+        .collect()
+}
+
+/// Runs a script and reads one of its globals, or the message of its first error
+pub fn global(source: &str, name: &str) -> Result<Option<Value>, String> {
+    let mut rox = Rox::new();
+    match rox.eval_str(source) {
+        Ok(()) => Ok(rox.get_global(name)),
+        Err(errors) => Err(errors[0].message.clone()),
+    }
+}
+
+/// Evaluates an expression by declaring a global with it
+pub fn eval(expr: &str) -> Result<Option<Value>, String> {
+    global(&format!("var result = {};", expr), "result")
+}
+
+pub fn string(value: &str) -> Option<Value> {
+    Some(Value::String(value.to_string()))
+}
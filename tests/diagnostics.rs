@@ -0,0 +1,141 @@
+mod common;
+
+use common::errors;
+use rox::ErrorKind;
+use rox::Renderer;
+use rox::RoxError;
+use rox::Span;
+
+fn render(error: &RoxError, source: &str) -> String {
+    Renderer::new(false).render(error, source, "test.rox")
+}
+
+#[test]
+fn span_is_underlined_below_its_line() {
+    let source = "var a = 1;\nprint a + nil;";
+    let errors = errors(source);
+
+    assert_eq!(
+        render(&errors[0], source),
+        "runtime error: Operands must be two numbers or two strings
+ --> test.rox:2:9
+  |
+2 | print a + nil;
+  |         ^
+stack trace:
+    at script (test.rox:2)
+"
+    );
+}
+
+#[test]
+fn markers_keep_tabs_so_they_line_up() {
+    let source = "\tif (true) {\n\t\tprint -\"a\";\n\t}";
+    let errors = errors(source);
+
+    assert_eq!(
+        render(&errors[0], source),
+        "runtime error: Operand must be a number
+ --> test.rox:2:9
+  |
+2 | \t\tprint -\"a\";
+  | \t\t      ^
+stack trace:
+    at script (test.rox:2)
+"
+    );
+}
+
+#[test]
+fn spans_over_several_lines_are_underlined_to_the_end_of_the_first() {
+    let source = "var s = \"one\ntwo\";";
+    let error = RoxError::new(
+        ErrorKind::Parse,
+        "Strings can't span lines".to_string(),
+        Span::new(8, 10, 1, 9),
+        None,
+    );
+
+    assert_eq!(
+        render(&error, source),
+        "syntax error: Strings can't span lines
+ --> test.rox:1:9
+  |
+1 | var s = \"one
+  |         ^^^^
+"
+    );
+}
+
+#[test]
+fn notes_point_at_their_own_span() {
+    let source = "{\n  var a = 1;\n  var a = 2;\n}";
+    let errors = errors(source);
+
+    assert_eq!(
+        render(&errors[0], source),
+        "error: Variable with this name already declared in this scope
+ --> test.rox:3:7
+  |
+3 |   var a = 2;
+  |       ^
+note: previously declared here
+ --> test.rox:2:7
+  |
+2 |   var a = 1;
+  |       -
+"
+    );
+}
+
+#[test]
+fn help_is_shown_after_the_snippet() {
+    let source = "class A {\n  f() {\n    super.f();\n  }\n}";
+    let errors = errors(source);
+
+    assert_eq!(
+        render(&errors[0], source),
+        "error: Cannot use 'super' in a class with no superclass
+ --> test.rox:3:5
+  |
+3 |     super.f();
+  |     ^^^^^
+ = help: add a superclass with 'class Name < Superclass'
+"
+    );
+}
+
+#[test]
+fn trace_lists_each_call_innermost_first() {
+    let source = "fun inner() {\n  return 1 + nil;\n}\n\nfun outer() {\n  inner();\n}\n\nouter();";
+    let errors = errors(source);
+
+    assert_eq!(
+        render(&errors[0], source),
+        "runtime error: Operands must be two numbers or two strings
+ --> test.rox:2:12
+  |
+2 |   return 1 + nil;
+  |            ^
+stack trace:
+    at inner() (test.rox:2)
+    at outer() (test.rox:6)
+    at script (test.rox:9)
+"
+    );
+}
+
+#[test]
+fn io_errors_have_no_snippet() {
+    let error = RoxError::new(
+        ErrorKind::Io,
+        "Cannot read 'missing.rox': No such file or directory".to_string(),
+        Span::default(),
+        None,
+    );
+
+    assert_eq!(
+        render(&error, ""),
+        "error: Cannot read 'missing.rox': No such file or directory\n"
+    );
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::global;
+use common::messages;
+use rox::Value;
+
+fn eval(source: &str) -> Result<Option<Value>, String> {
+    global(source, "s")
+}
+
+fn string(value: &str) -> Result<Option<Value>, String> {
+    Ok(common::string(value))
+}
+
+#[test]
+fn simple_escapes_are_decoded() {
+    assert_eq!(
+        eval(r#"var s = "a\tb\nc\\d\"e\$f";"#),
+        string("a\tb\nc\\d\"e$f")
+    );
+}
+
+#[test]
+fn unicode_escapes_are_decoded() {
+    assert_eq!(eval(r#"var s = "\u{48}\u{e9}\u{1F600}";"#), string("Hé😀"));
+}
+
+#[test]
+fn raw_strings_keep_backslashes_and_interpolations() {
+    assert_eq!(eval(r#"var s = r"a\nb${c}";"#), string("a\\nb${c}"));
+}
+
+#[test]
+fn invalid_escapes_are_reported() {
+    let message = |source| eval(source).unwrap_err();
+
+    assert_eq!(message(r#"var s = "\q";"#), "Invalid escape sequence");
+    assert_eq!(message(r#"var s = "\u48";"#), "Expect '{' after '\\u'");
+    assert_eq!(
+        message(r#"var s = "\u{48";"#),
+        "Expect '}' after unicode escape digits"
+    );
+    assert_eq!(
+        message(r#"var s = "\u{}";"#),
+        "Unicode escape must have 1 to 6 hex digits"
+    );
+    assert_eq!(
+        message(r#"var s = "\u{D800}";"#),
+        "'D800' is not a valid unicode scalar value"
+    );
+}
+
+#[test]
+fn every_invalid_escape_in_a_string_is_reported() {
+    assert_eq!(messages(r#"var s = "\q \w";"#).len(), 2);
+}
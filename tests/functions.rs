@@ -0,0 +1,52 @@
+mod common;
+
+use common::errors;
+use common::global;
+use rox::ErrorKind;
+use rox::RoxError;
+use rox::Value;
+
+/// Runs the script on a thread with a bigger stack than the test harness gives each test, as in a
+/// debug build the deepest calls the interpreter allows need more than that
+fn errors_with_big_stack(source: &'static str) -> Vec<RoxError> {
+    std::thread::Builder::new()
+        .stack_size(32 * 1024 * 1024)
+        .spawn(move || errors(source))
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+#[test]
+fn recursive_functions_return_their_result() {
+    assert_eq!(
+        global(
+            "fun fib(n) { if (n < 2) return n; return fib(n - 2) + fib(n - 1); } var result = fib(15);",
+            "result"
+        ),
+        Ok(Some(Value::Int(610)))
+    );
+}
+
+#[test]
+fn runaway_recursion_is_a_runtime_error() {
+    let errors = errors_with_big_stack("fun f(n) {\n  return f(n + 1);\n}\nf(0);");
+
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.kind, ErrorKind::Runtime);
+    assert_eq!(error.message, "Stack overflow");
+    assert_eq!(error.span.line, 2);
+    assert_eq!(error.trace.len(), 65);
+    assert_eq!(error.trace[0].function, "f()");
+    assert_eq!(error.trace[64].function, "script");
+    assert_eq!(error.trace[64].line, 4);
+}
+
+#[test]
+fn runaway_recursion_through_an_initializer_is_a_runtime_error() {
+    let errors = errors_with_big_stack("class A {\n  init() {\n    A();\n  }\n}\nA();");
+
+    assert_eq!(errors[0].kind, ErrorKind::Runtime);
+    assert_eq!(errors[0].message, "Stack overflow");
+}
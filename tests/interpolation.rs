@@ -0,0 +1,71 @@
+mod common;
+
+use common::messages;
+use common::run;
+use common::string;
+use rox::scanner::Scanner;
+use rox::scanner::TokenType;
+use rox::Value;
+
+fn types(source: &str) -> Vec<TokenType> {
+    let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+    tokens.into_iter().map(|token| token.type_).collect()
+}
+
+fn eval(source: &str) -> Option<Value> {
+    run(source).get_global("s")
+}
+
+#[test]
+fn string_is_split_around_each_expression() {
+    assert_eq!(
+        types(r#""a${x}b${y}c""#),
+        vec![
+            TokenType::InterpolationStart,
+            TokenType::Identifier,
+            TokenType::InterpolationMiddle,
+            TokenType::Identifier,
+            TokenType::InterpolationEnd,
+            TokenType::Eof,
+        ]
+    );
+}
+
+#[test]
+fn braces_inside_an_expression_do_not_end_it() {
+    assert_eq!(
+        types(r#""${f(fun() { return 1; })}""#)
+            .iter()
+            .filter(|type_| **type_ == TokenType::RightBrace)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn expressions_are_evaluated_and_joined() {
+    assert_eq!(
+        eval(r#"var x = 2; var s = "${x} + ${x} = ${x + x}";"#),
+        string("2 + 2 = 4")
+    );
+}
+
+#[test]
+fn interpolations_can_nest() {
+    assert_eq!(
+        eval(r#"var name = "rox"; var s = "outer ${"inner ${name}"} done";"#),
+        string("outer inner rox done")
+    );
+}
+
+#[test]
+fn escaped_dollar_is_not_an_interpolation() {
+    assert_eq!(eval(r#"var s = "\${x}";"#), string("${x}"));
+}
+
+#[test]
+fn unterminated_interpolation_is_reported() {
+    assert!(messages(r#"var s = "a${x";"#)
+        .iter()
+        .any(|message| message == "Unterminated string interpolation"));
+}
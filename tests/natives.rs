@@ -0,0 +1,63 @@
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use common::string;
+use rox::NativeError;
+use rox::Rox;
+use rox::Value;
+
+#[test]
+fn natives_can_capture_host_state() {
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let log = Rc::clone(&calls);
+
+    let mut rox = Rox::new();
+    rox.define_native("log", 1, move |arguments| {
+        log.borrow_mut().push(arguments[0].clone());
+        Ok(Value::Nil)
+    });
+    rox.eval_str(r#"log("first"); log(1 + 1);"#).unwrap();
+
+    assert_eq!(
+        *calls.borrow(),
+        vec![Value::String("first".to_string()), Value::Int(2)]
+    );
+}
+
+#[test]
+fn natives_keep_their_state_between_calls() {
+    let count = Rc::new(RefCell::new(0));
+
+    let mut rox = Rox::new();
+    rox.define_native("next", 0, move |_| {
+        *count.borrow_mut() += 1;
+        Ok(Value::Int(*count.borrow()))
+    });
+    rox.eval_str("next(); next(); var result = next();")
+        .unwrap();
+
+    assert_eq!(rox.get_global("result"), Some(Value::Int(3)));
+}
+
+#[test]
+fn native_errors_are_runtime_errors_at_the_call() {
+    let mut rox = Rox::new();
+    rox.define_native("fail", 0, |_| {
+        Err(NativeError::Failed("Out of widgets".to_string()))
+    });
+    let errors = rox.eval_str("var a = 1;\nfail();").unwrap_err();
+
+    assert_eq!(errors[0].message, "Out of widgets");
+    assert_eq!(errors[0].span.line, 2);
+}
+
+#[test]
+fn natives_are_values() {
+    let mut rox = Rox::new();
+    rox.define_native("greet", 0, |_| Ok(Value::String("hello".to_string())));
+    rox.eval_str("var f = greet; var result = f();").unwrap();
+
+    assert_eq!(rox.get_global("result"), string("hello"));
+}
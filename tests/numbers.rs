@@ -0,0 +1,52 @@
+mod common;
+
+use common::eval;
+use rox::Value;
+
+#[test]
+fn prefixed_literals_are_read_in_their_base() {
+    assert_eq!(eval("0x1F"), Ok(Some(Value::Int(31))));
+    assert_eq!(eval("0XfF"), Ok(Some(Value::Int(255))));
+    assert_eq!(eval("0b1010"), Ok(Some(Value::Int(10))));
+    assert_eq!(eval("0o17"), Ok(Some(Value::Int(15))));
+}
+
+#[test]
+fn separators_and_exponents_are_allowed() {
+    assert_eq!(eval("1_000_000"), Ok(Some(Value::Int(1_000_000))));
+    assert_eq!(eval("0xFF_FF"), Ok(Some(Value::Int(0xFFFF))));
+    assert_eq!(eval("1.5e3"), Ok(Some(Value::Double(1500.0))));
+    assert_eq!(eval("2E-2"), Ok(Some(Value::Double(0.02))));
+    assert_eq!(eval("1_0.2_5e+1"), Ok(Some(Value::Double(102.5))));
+}
+
+#[test]
+fn misplaced_separators_are_reported() {
+    let message = Err("Digit separator '_' must be between digits".to_string());
+    assert_eq!(eval("1__0"), message);
+    assert_eq!(eval("1_"), message);
+    assert_eq!(eval("0x_1"), message);
+    assert_eq!(eval("1_.5"), message);
+}
+
+#[test]
+fn malformed_literals_are_reported() {
+    assert_eq!(eval("0x"), Err("Expect digits after '0x'".to_string()));
+    assert_eq!(
+        eval("0b102"),
+        Err("Invalid digit '2' in binary literal".to_string())
+    );
+    assert_eq!(
+        eval("0o8"),
+        Err("Invalid digit '8' in octal literal".to_string())
+    );
+    assert_eq!(
+        eval("12z"),
+        Err("Invalid character 'z' in number literal".to_string())
+    );
+    assert_eq!(eval("1e"), Err("Expect digits in exponent".to_string()));
+    assert_eq!(
+        eval("1e999"),
+        Err("Number literal is too large".to_string())
+    );
+}
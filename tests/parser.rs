@@ -0,0 +1,24 @@
+mod common;
+
+use common::errors;
+use rox::ErrorKind;
+
+#[test]
+fn every_syntax_error_is_reported() {
+    let errors = errors("var a = ;\nprint 1;\nvar = 2;\nprint 2;\nfun f( {}\nprint 3;\nprint 4\n");
+
+    assert!(errors.iter().all(|error| error.kind == ErrorKind::Parse));
+    let found: Vec<(usize, &str)> = errors
+        .iter()
+        .map(|error| (error.span.line, error.message.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (1, "Expect expression"),
+            (3, "Expect variable name"),
+            (5, "Expect parameter name"),
+            (7, "Expect ';' after value"),
+        ]
+    );
+}
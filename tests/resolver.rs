@@ -0,0 +1,118 @@
+mod common;
+
+use common::errors;
+use common::run;
+use common::string;
+use rox::ErrorKind;
+use rox::Span;
+use rox::Value;
+
+#[test]
+fn reading_a_local_in_its_own_initializer_is_an_error() {
+    let errors = errors("{\n  var a = a;\n}");
+
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.kind, ErrorKind::Resolve);
+    assert_eq!(
+        error.message,
+        "Cannot read local variable in its own initializer"
+    );
+    assert_eq!(error.span, Span::new(12, 1, 2, 11));
+}
+
+#[test]
+fn declaring_a_variable_twice_in_one_scope_is_an_error() {
+    let errors = errors("{\n  var a = 1;\n  var a = 2;\n}");
+
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.kind, ErrorKind::Resolve);
+    assert_eq!(
+        error.message,
+        "Variable with this name already declared in this scope"
+    );
+    assert_eq!(error.span, Span::new(21, 1, 3, 7));
+}
+
+#[test]
+fn redeclaring_a_global_is_allowed() {
+    assert_eq!(
+        run("var a = 1; var a = 2;").get_global("a"),
+        Some(Value::Int(2))
+    );
+}
+
+#[test]
+fn returning_from_top_level_code_is_an_error() {
+    let errors = errors("print 1;\nreturn 2;");
+
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.kind, ErrorKind::Resolve);
+    assert_eq!(error.message, "Cannot return from top-level code");
+    assert_eq!(error.span, Span::new(9, 6, 2, 1));
+}
+
+#[test]
+fn every_resolve_error_is_reported() {
+    let errors = errors("return 1;\n{\n  var a = 1;\n  var a = a;\n}");
+
+    assert!(errors.iter().all(|error| error.kind == ErrorKind::Resolve));
+    let found: Vec<(usize, &str)> = errors
+        .iter()
+        .map(|error| (error.span.line, error.message.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (1, "Cannot return from top-level code"),
+            (4, "Variable with this name already declared in this scope"),
+            (4, "Cannot read local variable in its own initializer"),
+        ]
+    );
+}
+
+#[test]
+fn variables_are_bound_to_the_scope_they_resolve_to_when_declared() {
+    // 'a' is global when 'assign' is declared, so it keeps assigning to the global even once the
+    // block declares its own 'a'
+    let rox = run(r#"
+        var a = "global";
+        var inBlock;
+        {
+            fun assign() {
+                a = "assigned";
+            }
+
+            var a = "block";
+            assign();
+            inBlock = a;
+        }
+    "#);
+
+    assert_eq!(rox.get_global("a"), string("assigned"));
+    assert_eq!(rox.get_global("inBlock"), string("block"));
+}
+
+#[test]
+fn locals_are_found_through_several_enclosing_scopes() {
+    let rox = run(r#"
+        var result;
+        {
+            var a = "outer";
+            {
+                var b = "middle";
+                {
+                    var a = "inner";
+                    fun join() {
+                        return a + b;
+                    }
+                    result = join();
+                }
+            }
+        }
+    "#);
+
+    assert_eq!(rox.get_global("result"), string("innermiddle"));
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::errors;
+use common::eval;
+use common::global;
+use common::run;
+use common::string;
+use rox::scanner::Scanner;
+use rox::scanner::TokenType;
+use rox::Span;
+use rox::Value;
+
+#[test]
+fn identifiers_can_use_unicode_letters() {
+    let rox = run("var café = 1; var 名前 = 2; var ñandú_2 = café + 名前;");
+
+    assert_eq!(rox.get_global("café"), Some(Value::Int(1)));
+    assert_eq!(rox.get_global("名前"), Some(Value::Int(2)));
+    assert_eq!(rox.get_global("ñandú_2"), Some(Value::Int(3)));
+}
+
+#[test]
+fn characters_that_cannot_start_an_identifier_are_reported() {
+    let errors = errors("var a = 1;\nvar 😀 = 2;");
+
+    assert_eq!(errors[0].message, "Unexpected character");
+    assert_eq!(errors[0].lexeme.as_deref(), Some("😀"));
+    assert_eq!(errors[0].span, Span::new(15, 4, 2, 5));
+}
+
+#[test]
+fn emoji_and_cjk_strings_round_trip() {
+    assert_eq!(eval(r#""héllo, 世界 👋🏽""#), Ok(string("héllo, 世界 👋🏽")));
+    assert_eq!(eval(r#""日本" + "語 ✓""#), Ok(string("日本語 ✓")));
+}
+
+#[test]
+fn tokens_after_multibyte_characters_are_scanned_in_place() {
+    let tokens = Scanner::new("\"ü😀\" + é;".to_string())
+        .scan_tokens()
+        .unwrap();
+
+    let scanned: Vec<(TokenType, &str, usize, usize)> = tokens
+        .iter()
+        .map(|token| {
+            (
+                token.type_.clone(),
+                token.lexeme.as_str(),
+                token.span.offset,
+                token.span.column,
+            )
+        })
+        .collect();
+    assert_eq!(
+        scanned,
+        vec![
+            (TokenType::String, "\"ü😀\"", 0, 1),
+            (TokenType::Plus, "+", 9, 6),
+            (TokenType::Identifier, "é", 11, 8),
+            (TokenType::Semicolon, ";", 13, 9),
+            (TokenType::Eof, "", 14, 10),
+        ]
+    );
+}
+
+#[test]
+fn len_counts_unicode_scalar_values() {
+    assert_eq!(eval(r#"len("abc")"#), Ok(Some(Value::Int(3))));
+    assert_eq!(eval(r#"len("héllo")"#), Ok(Some(Value::Int(5))));
+    assert_eq!(eval(r#"len("世界😀")"#), Ok(Some(Value::Int(3))));
+    assert_eq!(eval(r#"len("")"#), Ok(Some(Value::Int(0))));
+    assert_eq!(
+        eval("len(1)"),
+        Err("Can only take the length of a string".to_string())
+    );
+}
+
+#[test]
+fn char_at_indexes_unicode_scalar_values() {
+    assert_eq!(eval(r#"charAt("héllo", 1)"#), Ok(string("é")));
+    assert_eq!(eval(r#"charAt("世界😀", 2)"#), Ok(string("😀")));
+    assert_eq!(eval(r#"charAt("世界😀", 1.0)"#), Ok(string("界")));
+    assert_eq!(
+        eval(r#"charAt("世界", 2)"#),
+        Err("String index 2 out of range".to_string())
+    );
+    assert_eq!(
+        eval(r#"charAt("世界", -1)"#),
+        Err("String index must be a whole number that isn't negative".to_string())
+    );
+    assert_eq!(
+        global(
+            r#"var s = "ab"; var result = charAt(s, len(s) - 1);"#,
+            "result"
+        ),
+        Ok(string("b"))
+    );
+}